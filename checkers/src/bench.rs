@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+use std::time::Instant;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::board::Board;
+use crate::controller::{CheckersColor, CheckersController};
+use crate::game::player::Player;
+use crate::stats::SearchStats;
+use crate::tournament::random_opening;
+
+pub fn bench_positions(count: usize, opening_plies: usize, seed: u64) -> Vec<CheckersController> {
+    //! The starting position followed by `count - 1` random openings, all with white to move.
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ret = vec![CheckersController::new(Board::default())];
+    while ret.len() < count {
+        ret.push(random_opening(opening_plies - opening_plies % 2, &mut rng));
+    }
+    ret.truncate(count);
+    ret
+}
+
+pub struct BenchResult {
+    pub name: String,
+    pub stats: SearchStats,
+    pub elapsed_ms: u64,
+    /// Score of the chosen action in every position, from white's point of view.
+    pub evaluations: Vec<Option<f64>>,
+}
+
+pub fn run_bench(name: &str, player: &mut dyn Player, positions: &[CheckersController]) -> BenchResult {
+    //! Lets the player choose an action for white in every position and sums up its search statistics.
+    player.set_color(CheckersColor::White);
+    let mut stats = SearchStats::default();
+    let mut evaluations = Vec::with_capacity(positions.len());
+    let started = Instant::now();
+    for controller in positions {
+        let (captures, moves) = controller.options(CheckersColor::White);
+        if !captures.is_empty() {
+            player.choose_capture(&captures, controller);
+        } else if !moves.is_empty() {
+            player.choose_move(&moves, controller);
+        }
+        if let Some(position_stats) = player.last_search_stats() {
+            stats += position_stats;
+        }
+        evaluations.push(player.last_evaluation());
+    }
+    let elapsed = started.elapsed();
+    stats.elapsed_us = elapsed.as_micros() as u64;
+    BenchResult { name: name.to_string(), stats, elapsed_ms: elapsed.as_millis() as u64, evaluations }
+}
+
+/// Benchmark results side by side, nodes relative to the first result.
+pub struct BenchReport {
+    pub results: Vec<BenchResult>,
+}
+
+impl Display for BenchReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let base = self.results.first().map(|result| result.stats.nodes.max(1)).unwrap_or(1);
+        writeln!(f, "{:<20} {:>14} {:>8} {:>12} {:>12} {:>10}", "search", "nodes", "ratio", "re-searches", "nodes/s", "time [ms]")?;
+        for result in &self.results {
+            writeln!(
+                f, "{:<20} {:>14} {:>7.1}% {:>12} {:>12.0} {:>10}",
+                result.name, result.stats.nodes, result.stats.nodes as f64 * 100.0 / base as f64,
+                result.stats.re_searches, result.stats.nodes_per_second(), result.elapsed_ms
+            )?;
+        }
+        if let Some(first) = self.results.first() {
+            let mismatches: Vec<&str> = self.results.iter()
+                .filter(|result| result.evaluations != first.evaluations)
+                .map(|result| result.name.as_str())
+                .collect();
+            if !mismatches.is_empty() {
+                writeln!(f, "scores differ from {}: {}", first.name, mismatches.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
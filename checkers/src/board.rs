@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
 use crate::controller::{CheckersColor, Figure};
 use crate::colors::colors as colors;
 
@@ -52,7 +53,7 @@ impl Square {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Board {
     pub occupation: u32,
     pub color: u32,
@@ -213,6 +214,19 @@ impl Board {
         (&self.occupation & &self.color & &self.figure).count_ones()
     }
 
+    pub fn position_hash(&self, side_to_move: CheckersColor) -> u64 {
+        //! Stable hash of the pieces and the side to move (flags are ignored), usable as a key in files.
+        fn mix(mut z: u64) -> u64 {
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+        let pieces = ((self.occupation as u64) << 32) | (self.occupation & self.color) as u64;
+        let queens = ((self.occupation & self.figure) as u64) << 1;
+        let side = if side_to_move.is_white() { 0 } else { 1 };
+        mix(mix(pieces) ^ queens ^ side)
+    }
+
 }
 
 impl Default for Board {
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::Rng;
+use crate::board::Board;
+use crate::controller::{CheckersColor, CheckersController};
+use crate::pdn::notation;
+use crate::record::RecordedAction;
+
+#[derive(Clone, Debug)]
+pub struct BookMove {
+    pub notation: String,
+    pub weight: f64,
+}
+
+#[derive(Debug)]
+pub struct BookError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// Maps `Board::position_hash` of a position to the actions worth playing there.
+///
+/// On disk every line holds one action: `<hash in hex> <notation> <weight>`, lines starting with `#` are comments.
+#[derive(Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn add(&mut self, key: u64, notation: String, weight: f64) {
+        let moves = self.entries.entry(key).or_default();
+        match moves.iter_mut().find(|m| m.notation == notation) {
+            Some(book_move) => book_move.weight += weight,
+            None => moves.push(BookMove { notation, weight }),
+        }
+    }
+
+    pub fn probe(&self, board: &Board, side_to_move: CheckersColor) -> Option<&[BookMove]> {
+        self.entries.get(&board.position_hash(side_to_move)).map(|moves| moves.as_slice())
+    }
+
+    pub fn choose<R: Rng>(&self, board: &Board, side_to_move: CheckersColor, candidates: &[String], rng: &mut R) -> Option<usize> {
+        //! Picks one of `candidates` (notations of the legal actions) with probability proportional to its book weight.
+        let book_moves = self.probe(board, side_to_move)?;
+        let weighted: Vec<(usize, f64)> = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, candidate)| {
+                book_moves.iter().find(|m| &m.notation == candidate).map(|m| (i, m.weight))
+            })
+            .filter(|&(_, weight)| weight > 0.0)
+            .collect();
+        let total: f64 = weighted.iter().map(|&(_, weight)| weight).sum();
+        if weighted.is_empty() || total <= 0.0 {
+            return None;
+        }
+        let mut pick = rng.gen_range(0.0..total);
+        for &(i, weight) in &weighted {
+            if pick < weight {
+                return Some(i);
+            }
+            pick -= weight;
+        }
+        weighted.last().map(|&(i, _)| i)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, BookError> {
+        let mut book = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| BookError { line: i + 1, message: format!("{message} in {line:?}") };
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() != 3 {
+                return Err(error("expected `<hash> <move> <weight>`"));
+            }
+            let key = u64::from_str_radix(parts[0], 16).map_err(|_| error("invalid hash"))?;
+            let weight = parts[2].parse::<f64>().map_err(|_| error("invalid weight"))?;
+            book.add(key, parts[1].to_string(), weight);
+        }
+        Ok(book)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for OpeningBook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+        writeln!(f, "# opening book: <position hash> <move> <weight>")?;
+        for key in keys {
+            for book_move in &self.entries[key] {
+                writeln!(f, "{key:016x} {} {}", book_move.notation, book_move.weight)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects the first plies of many games into an `OpeningBook`.
+///
+/// Every action is weighted by the points it brought to the side that played it
+/// (1 for a win, 0.5 for a draw), actions seen in fewer than `min_games` games are dropped.
+pub struct BookBuilder {
+    max_plies: usize,
+    min_games: usize,
+    stats: HashMap<(u64, String), (usize, f64)>,
+}
+
+impl BookBuilder {
+    pub fn new(max_plies: usize, min_games: usize) -> Self {
+        Self { max_plies, min_games, stats: HashMap::new() }
+    }
+
+    pub fn add_game(
+        &mut self,
+        initial: &CheckersController,
+        first_to_move: CheckersColor,
+        actions: &[RecordedAction],
+        winner: Option<CheckersColor>,
+    ) {
+        //! `winner` is `None` for a draw.
+        let mut controller = initial.clone();
+        let mut color = first_to_move;
+        for action in actions.iter().take(self.max_plies) {
+            let points = match winner {
+                Some(winner) if winner == color => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            let entry = self.stats
+                .entry((controller.board.position_hash(color), notation(action)))
+                .or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += points;
+            controller.play(&action.into());
+            color = color.opposite();
+        }
+    }
+
+    pub fn build(&self) -> OpeningBook {
+        let mut book = OpeningBook::new();
+        for ((key, notation), &(games, points)) in &self.stats {
+            if games >= self.min_games && points > 0.0 {
+                book.add(*key, notation.clone(), points);
+            }
+        }
+        book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdn::parse_pdn;
+
+    #[test]
+    fn book_round_trip() {
+        let games = parse_pdn("1. c3-d4 f6-e5 2. d4xf6 g7xe5 1-0\n1. c3-d4 b6-a5 *").expect("valid games");
+        let mut builder = BookBuilder::new(2, 1);
+        for game in &games {
+            builder.add_game(&game.initial, game.first_to_move, &game.actions, game.result.flatten());
+        }
+        let book = builder.build();
+        let start = book.probe(&Board::default(), CheckersColor::White).expect("the first move is in the book");
+        assert_eq!(start.len(), 1);
+        assert_eq!((start[0].notation.as_str(), start[0].weight), ("c3-d4", 1.5));
+
+        let parsed = OpeningBook::parse(&book.to_string()).expect("written books should parse");
+        assert_eq!(parsed.len(), book.len());
+        assert_eq!(parsed.to_string(), book.to_string());
+        let error = OpeningBook::parse("# comment\n00ff c3-d4\n").err().expect("the weight is missing");
+        assert_eq!(error.line, 2);
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use crate::game::estimators::{phase, BoardEstimator, Position, ScoreTerm};
+use crate::tune::Tunable;
+
+/// One estimator of a `WeightedSum` with the weight its score is multiplied by.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component<E> {
+    pub weight: f64,
+    pub estimator: E,
+}
+
+/// Sum of estimators each multiplied by its weight. The components are boxed by default so estimators of
+/// different types can be mixed, `WeightedSum<T>` sums estimators of one type without the indirection and
+/// `WeightedSum<EstimatorConfig>` is the one read from config files.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightedSum<E = Box<dyn BoardEstimator + Send + Sync>> {
+    components: Vec<Component<E>>,
+}
+
+impl <E> Default for WeightedSum<E> {
+    fn default() -> Self {
+        Self { components: Vec::new() }
+    }
+}
+
+impl <E> WeightedSum<E> {
+    pub fn with(mut self, weight: f64, estimator: E) -> Self {
+        self.components.push(Component { weight, estimator });
+        self
+    }
+
+    pub fn components(&self) -> &[Component<E>] {
+        &self.components
+    }
+}
+
+// the command line builds its mixed sums from `EstimatorConfig`s, boxed ones keep the tests short
+#[cfg(test)]
+impl WeightedSum {
+    pub fn with_boxed<T: BoardEstimator + Send + Sync + 'static>(self, weight: f64, estimator: T) -> Self {
+        self.with(weight, Box::new(estimator))
+    }
+}
+
+impl <E: BoardEstimator> BoardEstimator for WeightedSum<E> {
+    fn score(&self, position: &Position) -> f64 {
+        self.components.iter().map(|component| component.weight * component.estimator.score(position)).sum()
+    }
+
+    fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+        //! The terms of all components with their weights multiplied by the component's weight.
+        self.components
+            .iter()
+            .flat_map(|Component { weight, estimator }| {
+                estimator.explain(position).into_iter().map(move |term| ScoreTerm { weight: term.weight * weight, ..term })
+            })
+            .collect()
+    }
+}
+
+impl <E: Tunable> Tunable for WeightedSum<E> {
+    fn parameters(&self) -> Vec<f64> {
+        //! The weight of every component followed by the parameters of its estimator.
+        self.components
+            .iter()
+            .flat_map(|component| std::iter::once(component.weight).chain(component.estimator.parameters()))
+            .collect()
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        let mut rest = parameters;
+        self.components.iter().fold(Self::default(), |sum, component| {
+            let (own, tail) = rest[1..].split_at(component.estimator.parameters().len());
+            let weight = rest[0];
+            rest = tail;
+            sum.with(weight, component.estimator.with_parameters(own))
+        })
+    }
+}
+
+/// Blends an opening and an endgame estimator by the game phase: the opening estimator alone with all
+/// 24 pieces on the board, the endgame estimator alone with none.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhaseBlend<O, E> {
+    opening: O,
+    endgame: E,
+}
+
+impl <O, E> PhaseBlend<O, E> {
+    pub fn new(opening: O, endgame: E) -> Self {
+        Self { opening, endgame }
+    }
+
+    pub fn opening(&self) -> &O {
+        &self.opening
+    }
+
+    pub fn endgame(&self) -> &E {
+        &self.endgame
+    }
+}
+
+impl <O: BoardEstimator, E: BoardEstimator> BoardEstimator for PhaseBlend<O, E> {
+    fn score(&self, position: &Position) -> f64 {
+        let phase = phase(&position.board);
+        phase * self.opening.score(position) + (1.0 - phase) * self.endgame.score(position)
+    }
+
+    fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+        //! The terms of both estimators, prefixed with the phase they belong to and weighted by their share.
+        let phase = phase(&position.board);
+        let parts: [(&str, f64, &dyn BoardEstimator); 2] = [
+            ("opening", phase, &self.opening),
+            ("endgame", 1.0 - phase, &self.endgame),
+        ];
+        parts
+            .into_iter()
+            .flat_map(|(prefix, share, estimator)| {
+                estimator.explain(position).into_iter().map(move |term| ScoreTerm {
+                    name: format!("{prefix} {}", term.name),
+                    weight: term.weight * share,
+                    ..term
+                })
+            })
+            .collect()
+    }
+}
+
+impl <O: Tunable, E: Tunable> Tunable for PhaseBlend<O, E> {
+    fn parameters(&self) -> Vec<f64> {
+        //! The parameters of the opening estimator followed by those of the endgame estimator.
+        let mut parameters = self.opening.parameters();
+        parameters.extend(self.endgame.parameters());
+        parameters
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        let (opening, endgame) = parameters.split_at(self.opening.parameters().len());
+        Self::new(self.opening.with_parameters(opening), self.endgame.with_parameters(endgame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::controller::CheckersColor;
+    use crate::game::estimators::{CountEstimator, MobilityEstimator, PositionalEstimator};
+    use crate::tune::Tunable;
+
+    fn position() -> Position {
+        // the starting position without black's pawn on b6, so no estimator scores it 0
+        let mut board = Board::default();
+        board.set(1, 5, None);
+        Position::from_board(board, CheckersColor::White)
+    }
+
+    #[test]
+    fn boxed_sum_mixes_estimator_types() {
+        let count = CountEstimator::new(1.0, 3.0);
+        let positional = PositionalEstimator::default();
+        let sum = WeightedSum::default().with_boxed(2.0, count).with_boxed(0.5, positional).with_boxed(0.1, MobilityEstimator);
+        let position = position();
+        let expected = 2.0 * count.score(&position) + 0.5 * positional.score(&position) + 0.1 * MobilityEstimator.score(&position);
+        assert!((sum.score(&position) - expected).abs() < 1e-12);
+        assert_eq!(sum.explain(&position).len(), count.explain(&position).len() + positional.explain(&position).len() + 1);
+    }
+
+    #[test]
+    fn blend_follows_the_phase() {
+        let (opening, endgame) = (CountEstimator::new(1.0, 3.0), CountEstimator::new(2.0, 5.0));
+        let blend = PhaseBlend::new(opening, endgame);
+        let position = position();
+        let phase = 23.0 / 24.0;
+        let expected = phase * opening.score(&position) + (1.0 - phase) * endgame.score(&position);
+        assert!((blend.score(&position) - expected).abs() < 1e-12);
+        let terms = blend.explain(&position);
+        let names: Vec<&str> = terms.iter().map(|term| term.name.as_str()).collect();
+        assert_eq!(names, ["opening pawns", "opening queens", "endgame pawns", "endgame queens"]);
+        assert!((terms[0].weight - phase).abs() < 1e-12);
+        assert!((terms[3].weight - (1.0 - phase) * 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn parameters_round_trip() {
+        let sum = WeightedSum::default().with(1.0, CountEstimator::new(1.0, 3.0)).with(0.5, CountEstimator::new(2.0, 5.0));
+        assert_eq!(sum.parameters(), vec![1.0, 1.0, 3.0, 0.5, 2.0, 5.0]);
+        let changed = sum.with_parameters(&[2.0, 1.5, 4.0, 0.25, 1.0, 6.0]);
+        assert_eq!(changed.parameters(), vec![2.0, 1.5, 4.0, 0.25, 1.0, 6.0]);
+        assert_eq!(changed.score(&position()), 2.0 * (1.5 * 1.0) + 0.25 * (1.0 * 1.0));
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::composite::{PhaseBlend, WeightedSum};
+use crate::game::estimators::{BoardEstimator, CountEstimator, MobilityEstimator, Position, PositionalEstimator, ScoreTerm, WeightMatrixEstimator};
+use crate::learn::LinearEstimator;
+use crate::tune::Tunable;
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// An estimator with its weights as stored in a config file, the `type` key selects the estimator and the
+/// other keys are its fields. Files ending in `.toml` are TOML, all others JSON.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum EstimatorConfig {
+    Count(CountEstimator),
+    Matrix(Box<WeightMatrixEstimator>),
+    Linear(LinearEstimator),
+    Mobility(MobilityEstimator),
+    Positional(PositionalEstimator),
+    WeightedSum(WeightedSum<EstimatorConfig>),
+    PhaseBlend(PhaseBlend<Box<EstimatorConfig>, Box<EstimatorConfig>>),
+}
+
+impl EstimatorConfig {
+    /// Names of the configs shipped in `estimators/`.
+    pub const BUILTIN: [&'static str; 5] = ["count", "matrix", "positional", "composite", "phased"];
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        let text = match name {
+            "count" => include_str!("../estimators/count.toml"),
+            "matrix" => include_str!("../estimators/matrix.toml"),
+            "positional" => include_str!("../estimators/positional.toml"),
+            "composite" => include_str!("../estimators/composite.toml"),
+            "phased" => include_str!("../estimators/phased.toml"),
+            _ => return None,
+        };
+        Some(Self::from_toml(text).unwrap_or_else(|e| panic!("invalid built-in estimator {name:?}: {e}")))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text).map_err(|e| ConfigError::new(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(text).map_err(|e| ConfigError::new(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("estimator configs are representable in TOML")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("estimator configs are representable in JSON")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::new(format!("failed to read {path:?}: {e}")))?;
+        if is_toml(path) { Self::from_toml(&text) } else { Self::from_json(&text) }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let text = if is_toml(path) { self.to_toml() } else { self.to_json() };
+        fs::write(path, text).map_err(|e| ConfigError::new(format!("failed to write {path:?}: {e}")))
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Count(_) => "count",
+            Self::Matrix(_) => "matrix",
+            Self::Linear(_) => "linear",
+            Self::Mobility(_) => "mobility",
+            Self::Positional(_) => "positional",
+            Self::WeightedSum(_) => "weighted-sum",
+            Self::PhaseBlend(_) => "phase-blend",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        //! Checks what deserializing does not: all weights are finite numbers and weighted sums are not empty.
+        //! Errors name the path to the offending estimator, e.g. `components[1].estimator`.
+        let nested = |path: String, config: &Self| {
+            config.validate().map_err(|e| ConfigError::new(format!("{path}: {e}")))
+        };
+        if let Self::Matrix(matrix) = self {
+            // the tuned parameters leave out the light squares and black's table, which are still read
+            let tables = std::iter::once(("board_weights", matrix.board_weights()))
+                .chain(matrix.black_weights().map(|weights| ("black_weights", weights)));
+            for (name, weights) in tables {
+                for (x, column) in weights.iter().enumerate() {
+                    if let Some(y) = column.iter().position(|weight| !weight.is_finite()) {
+                        return Err(ConfigError::new(format!("matrix estimator: {name}[{x}][{y}] is not a finite number")));
+                    }
+                }
+            }
+        }
+        match self {
+            Self::WeightedSum(sum) => {
+                if sum.components().is_empty() {
+                    return Err(ConfigError::new(String::from("weighted-sum needs at least one component")));
+                }
+                for (i, component) in sum.components().iter().enumerate() {
+                    if !component.weight.is_finite() {
+                        return Err(ConfigError::new(format!("components[{i}]: weight is not a finite number")));
+                    }
+                    nested(format!("components[{i}].estimator"), &component.estimator)?;
+                }
+                Ok(())
+            }
+            Self::PhaseBlend(blend) => {
+                nested(String::from("opening"), blend.opening())?;
+                nested(String::from("endgame"), blend.endgame())
+            }
+            _ => match self.parameters().iter().position(|parameter| !parameter.is_finite()) {
+                Some(i) => Err(ConfigError::new(format!("{} estimator: weight #{i} is not a finite number", self.kind()))),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn estimator(&self) -> &dyn BoardEstimator {
+        match self {
+            Self::Count(estimator) => estimator,
+            Self::Matrix(estimator) => estimator,
+            Self::Linear(estimator) => estimator,
+            Self::Mobility(estimator) => estimator,
+            Self::Positional(estimator) => estimator,
+            Self::WeightedSum(estimator) => estimator,
+            Self::PhaseBlend(estimator) => estimator,
+        }
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "toml")
+}
+
+impl BoardEstimator for EstimatorConfig {
+    fn score(&self, position: &Position) -> f64 {
+        self.estimator().score(position)
+    }
+
+    fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+        self.estimator().explain(position)
+    }
+}
+
+impl Tunable for EstimatorConfig {
+    fn parameters(&self) -> Vec<f64> {
+        match self {
+            Self::Count(estimator) => estimator.parameters(),
+            Self::Matrix(estimator) => estimator.parameters(),
+            Self::Linear(estimator) => estimator.parameters(),
+            Self::Mobility(estimator) => estimator.parameters(),
+            Self::Positional(estimator) => estimator.parameters(),
+            Self::WeightedSum(estimator) => estimator.parameters(),
+            Self::PhaseBlend(estimator) => estimator.parameters(),
+        }
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        match self {
+            Self::Count(estimator) => Self::Count(estimator.with_parameters(parameters)),
+            Self::Matrix(estimator) => Self::Matrix(estimator.with_parameters(parameters)),
+            Self::Linear(estimator) => Self::Linear(estimator.with_parameters(parameters)),
+            Self::Mobility(estimator) => Self::Mobility(estimator.with_parameters(parameters)),
+            Self::Positional(estimator) => Self::Positional(estimator.with_parameters(parameters)),
+            Self::WeightedSum(estimator) => Self::WeightedSum(estimator.with_parameters(parameters)),
+            Self::PhaseBlend(estimator) => Self::PhaseBlend(estimator.with_parameters(parameters)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(estimator: WeightMatrixEstimator) -> EstimatorConfig {
+        EstimatorConfig::Matrix(Box::new(estimator))
+    }
+
+    #[test]
+    fn builtins_are_valid() {
+        for name in EstimatorConfig::BUILTIN {
+            let config = EstimatorConfig::builtin(name).unwrap();
+            assert!(config.validate().is_ok(), "{name}");
+            assert_eq!(EstimatorConfig::from_toml(&config.to_toml()).unwrap().parameters(), config.parameters(), "{name}");
+        }
+    }
+
+    #[test]
+    fn every_matrix_entry_is_checked() {
+        let weights = [[1.0; 8]; 8];
+        assert!(matrix(WeightMatrixEstimator::new(weights, 1.0, 3.0)).validate().is_ok());
+
+        let mut light_square = weights;
+        light_square[0][1] = f64::NAN;
+        let error = matrix(WeightMatrixEstimator::new(light_square, 1.0, 3.0)).validate().unwrap_err();
+        assert_eq!(error.message, "matrix estimator: board_weights[0][1] is not a finite number");
+
+        let mut black = weights;
+        black[4][6] = f64::INFINITY;
+        let error = matrix(WeightMatrixEstimator::new(weights, 1.0, 3.0).with_black_weights(Some(black))).validate().unwrap_err();
+        assert_eq!(error.message, "matrix estimator: black_weights[4][6] is not a finite number");
+
+        let error = matrix(WeightMatrixEstimator::new(weights, f64::NAN, 3.0)).validate().unwrap_err();
+        assert_eq!(error.message, "matrix estimator: weight #0 is not a finite number");
+    }
+
+    #[test]
+    fn black_weights_survive_toml() {
+        let mut black = [[1.0; 8]; 8];
+        black[2][4] = 0.5;
+        let config = matrix(WeightMatrixEstimator::new([[1.0; 8]; 8], 1.0, 3.0).with_black_weights(Some(black)));
+        let EstimatorConfig::Matrix(loaded) = EstimatorConfig::from_toml(&config.to_toml()).unwrap() else { panic!() };
+        assert_eq!(loaded.black_weights(), Some(&black));
+
+        let text = config.to_toml().replace("0.5", "nan");
+        let error = EstimatorConfig::from_toml(&text).unwrap_err();
+        assert!(error.message.contains("black_weights[2][4]"), "{error}");
+    }
+}
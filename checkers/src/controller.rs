@@ -1,7 +1,8 @@
 use std::fmt::{write, Display, Formatter};
+use serde::{Deserialize, Serialize};
 use crate::board::{Board, alias};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CheckersColor{
     White, Black
 }
@@ -75,7 +76,7 @@ pub trait CheckersAct: Into<CheckersAction> {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Move {
     x_start: u8,
     y_start: u8,
@@ -113,7 +114,7 @@ impl Display for Move {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Jump {
     x_start: u8,
     y_start: u8,
@@ -167,7 +168,7 @@ impl Display for Jump {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct JumpChain(Vec<Jump>);
 
 impl JumpChain {
@@ -175,6 +176,10 @@ impl JumpChain {
         self.0.len()
     }
 
+    pub fn jumps(&self) -> &[Jump] {
+        &self.0
+    }
+
     pub fn start_position(&self) -> (u8, u8) {
         self.0.first().expect("JumpChain should have at least one element").start_position()
     }
@@ -184,6 +189,12 @@ impl JumpChain {
     }
 }
 
+impl From<Jump> for JumpChain {
+    fn from(jump: Jump) -> Self {
+        Self(vec![jump])
+    }
+}
+
 impl Display for JumpChain {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.0.is_empty() { return write!(f, "[]") };
@@ -211,6 +222,7 @@ impl Display for JumpChain {
 //     }
 // }
 
+#[derive(Clone, Debug)]
 pub enum CheckersAction {
     Jump(Jump), Move(Move), JumpChain(JumpChain)
 }
@@ -233,6 +245,7 @@ impl CheckersAction {
     }
 }
 
+#[derive(Clone)]
 pub struct CheckersController {
     pub board: Board,
     white_queen_idle_moves: u8,
@@ -312,6 +325,15 @@ impl CheckersController {
         (jumps, moves)
     }
 
+    pub fn actions(&self, color: CheckersColor) -> Vec<CheckersAction> {
+        //! `options` as one list: the captures, or the moves when no capture is possible.
+        let (jumps, moves) = self.options(color);
+        if !jumps.is_empty() {
+            return jumps.into_iter().map(CheckersAction::JumpChain).collect();
+        }
+        moves.into_iter().map(CheckersAction::Move).collect()
+    }
+
     // moves
 
     pub fn can_move(&self, x: u8, y: u8) -> bool {
@@ -589,6 +611,15 @@ impl CheckersController {
         }
     }
 
+    pub fn play(&mut self, action: &CheckersAction) {
+        //! Executes the action the way `Game` does: clears capture flags and promotes pawns afterwards.
+        self.execute_action(action);
+        if let CheckersAction::JumpChain(_) = action {
+            self.board.flags = 0;
+        }
+        self.promote();
+    }
+
     fn in_bounds(x: i8, y: i8) -> bool {
         if y > 7 || x > 7 { return false; }
         if y < 0 || x < 0 { return false; }
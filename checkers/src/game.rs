@@ -1,50 +1,75 @@
-use std::fmt::Display;
-use std::thread;
-use std::time::Duration;
-use crate::controller::{CheckersController, CheckersColor, Figure};
-use rand::seq::SliceRandom;
+use std::time::Instant;
+use crate::controller::{CheckersController, CheckersColor};
 use crate::game::player::Player;
+use crate::observer::{ConsoleObserver, GameObserver, SearchInfo};
+use crate::scheduler::derive_seed;
+use crate::record::{unix_millis, EndReason, GameOutcome, GameRecord, RecordedAction, RecordedPly};
 
 pub mod player {
     use std::cmp::max;
     use std::fmt::Display;
     use std::io;
-    use std::sync::{Arc, Mutex};
+    use std::sync::Arc;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rayon::ThreadPool;
     use rand::seq::SliceRandom;
-    use rayon::prelude::*;
     use crate::board::Board;
-    use crate::controller::{CheckersColor, CheckersController, JumpChain, Move};
+    use crate::controller::{CheckersAction, CheckersColor, CheckersController, JumpChain, Move};
+    use crate::book::OpeningBook;
     use crate::game::estimators::BoardEstimator;
-
-    pub trait Player {
-        fn choose_move<'a>(&'a self, moves: &'a [Move], board: Board) -> &Move;
-        fn choose_capture<'a>(&'a self, captures: &'a [JumpChain], board: Board) -> &JumpChain;
+    use crate::pdn::{capture_notation, move_notation};
+    use crate::ponder::Ponder;
+    use crate::search::{choose_best, RootSearch, SearchEngine};
+    use crate::skill::SkillLevel;
+    use crate::stats::SearchStats;
+    use crate::tablebase::Tablebase;
+    use crate::tt::TranspositionTable;
+
+    pub trait Player: Send {
+        /// `controller` is the position to move in, with the idle queen move counters of the game.
+        fn choose_move<'a>(&mut self, moves: &'a [Move], controller: &CheckersController) -> &'a Move;
+        fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], controller: &CheckersController) -> &'a JumpChain;
         fn get_color(&self) -> CheckersColor;
         fn set_color(&mut self, color: CheckersColor);
+        fn name(&self) -> String;
+
+        fn reseed(&mut self, _seed: u64) {
+            //! Reseeds the random number generator used by the player, players without randomness ignore it.
+        }
+
+        fn last_evaluation(&self) -> Option<f64> {
+            //! Score of the last chosen action from white's point of view, if the player computes one.
+            None
+        }
+
+        fn last_search_stats(&self) -> Option<SearchStats> {
+            //! Statistics of the search behind the last chosen action, if the player searches.
+            None
+        }
     }
 
     pub struct DummyBot {
-        color: CheckersColor
+        color: CheckersColor,
+        rng: StdRng,
     }
 
     impl DummyBot {
-        pub fn new() -> Self { Self { color: CheckersColor::White } }
+        pub fn new() -> Self { Self { color: CheckersColor::White, rng: StdRng::from_entropy() } }
+
+        pub fn with_seed(seed: u64) -> Self { Self { color: CheckersColor::White, rng: StdRng::seed_from_u64(seed) } }
 
     }
 
     impl Player for DummyBot {
-        fn choose_move<'a>(&'a self, moves: &'a [Move], board: Board) -> &Move {
-            let m = moves
-                .choose(&mut rand::thread_rng())
-                .expect("moves should contain at least one move");
-            println!("{m}");
-            m
+        fn choose_move<'a>(&mut self, moves: &'a [Move], _controller: &CheckersController) -> &'a Move {
+            moves
+                .choose(&mut self.rng)
+                .expect("moves should contain at least one move")
         }
 
-        fn choose_capture<'a>(&'a self, captures: &'a [JumpChain], board: Board) -> &JumpChain {
-            let c = captures.choose(&mut rand::thread_rng()).unwrap();
-            println!("{c}");
-            c
+        fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], _controller: &CheckersController) -> &'a JumpChain {
+            captures.choose(&mut self.rng).unwrap()
         }
 
         fn set_color(&mut self, color: CheckersColor) {
@@ -55,7 +80,13 @@ pub mod player {
             self.color
         }
 
+        fn name(&self) -> String {
+            String::from("DummyBot")
+        }
 
+        fn reseed(&mut self, seed: u64) {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
     }
 
     pub struct HumanPlayer {
@@ -67,7 +98,7 @@ pub mod player {
     }
 
     impl Player for HumanPlayer {
-        fn choose_move<'a>(&'a self, moves: &'a [Move], board: Board) -> &Move {
+        fn choose_move<'a>(&mut self, moves: &'a [Move], _controller: &CheckersController) -> &'a Move {
             for (i, move_) in moves.iter().enumerate() {
                 println!("{i}. {move_}");
             }
@@ -75,7 +106,7 @@ pub mod player {
             &moves[index]
         }
 
-        fn choose_capture<'a>(&'a self, captures: &'a [JumpChain], board: Board) -> &JumpChain {
+        fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], _controller: &CheckersController) -> &'a JumpChain {
             for (i, jump_chain) in captures.iter().enumerate() {
                 println!("{i}. {jump_chain}");
             }
@@ -90,6 +121,10 @@ pub mod player {
         fn set_color(&mut self, color: CheckersColor) {
             self.color = color
         }
+
+        fn name(&self) -> String {
+            String::from("HumanPlayer")
+        }
     }
 
     impl HumanPlayer {
@@ -121,482 +156,198 @@ pub mod player {
     }
 
     pub struct MinMaxBot<T> {
-        estimator: T,
-        depth: usize,
+        engine: SearchEngine<T>,
         color: CheckersColor,
-        nodes_visited: Arc<Mutex<usize>>
+        last_stats: Option<SearchStats>,
+        last_evaluation: Option<f64>,
+        rng: StdRng,
     }
 
     impl <T> MinMaxBot<T> {
-
-        pub const MIN_SCORE: f64 = -1e10;
-        pub const MAX_SCORE: f64 = 1e10;
-
-        pub fn new(estimator: T, depth: usize) -> Self { Self{estimator, depth, color: CheckersColor::White, nodes_visited: Arc::new(Mutex::new(0))} }
-
-        fn update_estimate(&self, est: f64, current: f64, color: CheckersColor) -> f64 {
-            if color.is_white() {
-                if est > current {
-                    return est;
-                }
-            } else {
-                if est < current {
-                    return est;
-                }
-            }
-            current
+        pub fn new(estimator: T, depth: usize) -> Self {
+            Self{engine: SearchEngine::minimax(estimator, depth), color: CheckersColor::White, last_stats: None, last_evaluation: None, rng: StdRng::from_entropy()}
         }
     }
 
-    impl <T: BoardEstimator> MinMaxBot<T> {
-        fn minmax(&self, controller: &CheckersController, depth: usize, current_color: CheckersColor) -> f64 {
-            if depth == 0 {
-                {
-                    let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                    *nodes_visited += 1;
-                }
-                return self.estimator.score(&controller.board);
-            }
-            let idle_moves = match self.color {
-                CheckersColor::White => controller.get_white_queen_idle_moves(),
-                CheckersColor::Black => controller.get_black_queen_idle_moves(),
-            };
-            if idle_moves > 8 {
-                return if current_color.is_white() { Self::MIN_SCORE } else { Self::MAX_SCORE };
-            }
-            let (jumps, moves) = controller.options(current_color);
-            if !jumps.is_empty() {
-                return self.minmax_jumps(controller, &jumps, depth, current_color);
-            }
-            if !moves.is_empty() {
-                return self.minmax_moves(controller, &moves, depth, current_color);
-            }
-            if current_color.is_white() { Self::MIN_SCORE } else { Self::MAX_SCORE }
-        }
-
-        fn minmax_jumps(&self, controller: &CheckersController, captures: &[JumpChain], depth: usize, current_color: CheckersColor) -> f64 {
-            let mut current = if current_color.is_white() { f64::MIN } else { f64::MAX };
-            for capture in captures {
-                let mut controller = CheckersController::with_idle_moves(
-                    controller.board,
-                    controller.get_white_queen_idle_moves(),
-                    controller.get_black_queen_idle_moves()
-                );
-                controller.execute_capture(capture);
-                let est = self.minmax(&controller, depth - 1, current_color.opposite());
-                current = self.update_estimate(est, current, current_color);
-            }
-            current
-        }
-
-        fn minmax_moves(&self, controller: &CheckersController, moves: &[Move], depth: usize, current_color: CheckersColor) -> f64 {
-            let mut current = if current_color.is_white() { f64::MIN } else { f64::MAX };
-            for move_ in moves {
-                let mut controller = CheckersController::with_idle_moves(
-                    controller.board,
-                    controller.get_white_queen_idle_moves(),
-                    controller.get_black_queen_idle_moves(),
-                );
-                controller.execute_move(move_);
-                let est = self.minmax(&controller, depth - 1, current_color.opposite());
-                current = self.update_estimate(est, current, current_color);
-            }
-            current
-        }
-
-        fn get_best_eval(&self, evals: &Vec<(usize, f64)>) -> f64 {
-            if self.color.is_white() {
-                evals
-                    .iter()
-                    .map(|(_, eval)| eval)
-                    .cloned()
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(f64::MIN)
-            } else {
-                evals
-                    .iter()
-                    .map(|(_, eval)| eval)
-                    .cloned()
-                    .min_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(f64::MAX)
+    impl <T: BoardEstimator + Sync + Send> MinMaxBot<T> {
+        fn choose(&mut self, actions: &[CheckersAction], controller: &CheckersController) -> usize {
+            self.last_evaluation = None;
+            self.last_stats = None;
+            if actions.len() == 1 {
+                return 0;
             }
+            let (scores, stats) = self.engine.score_actions(controller, actions, self.get_color());
+            let (i, best_eval) = choose_best(&scores, self.color, &mut self.rng);
+            self.last_evaluation = Some(best_eval);
+            self.last_stats = Some(stats);
+            i
         }
+    }
 
-        fn get_best_indices(&self, evals: &Vec<(usize, f64)>, best: f64) -> Vec<usize> {
-            evals
-                .iter()
-                .filter(|(i, eval)| (eval - best).abs() < f64::EPSILON)
-                .map(|(i, _)| *i)
-                .collect()
+    impl <T: BoardEstimator + Sync + Send> Player for MinMaxBot<T> {
+        fn choose_move<'a>(&mut self, moves: &'a [Move], controller: &CheckersController) -> &'a Move {
+            let actions: Vec<CheckersAction> = moves.iter().map(|&move_| CheckersAction::Move(move_)).collect();
+            &moves[self.choose(&actions, controller)]
         }
 
-        fn get_at_indices<'a, U>(&'a self, indices: &[usize], values: &'a [U]) -> Vec<&U> {
-            let mut ret = Vec::with_capacity(indices.len());
-            for &i in indices {
-                ret.push(&values[i])
-            }
-            ret
+        fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], controller: &CheckersController) -> &'a JumpChain {
+            let actions: Vec<CheckersAction> = captures.iter().cloned().map(CheckersAction::JumpChain).collect();
+            &captures[self.choose(&actions, controller)]
         }
-    }
-
-    impl <T: BoardEstimator + Sync> Player for MinMaxBot<T> {
-        fn choose_move<'a>(&'a self, moves: &'a [Move], board: Board) -> &Move {
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                *nodes_visited = 0;
-            }
-            if moves.len() == 1 {
-                return moves.first().unwrap()
-            }
 
-            let moves_eval: Vec<(usize, f64)> = moves
-                .par_iter()
-                .enumerate()
-                .map(|(i, move_)| {
-                    let mut controller = CheckersController::new(board);
-                    controller.execute_move(move_);
-                    let eval = self.minmax(&controller, self.depth -1, self.get_color().opposite());
-                    (i, eval)
-                }).collect();
-            let best_eval = self.get_best_eval(&moves_eval);
-            let indices = self.get_best_indices(&moves_eval, best_eval);
-            let best_moves = self.get_at_indices(&indices, moves);
-
-            println!("{:?} MinMaxBot best: {}", self.color, best_eval * if self.color.is_white() {1.0} else {-1.0});
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                println!("Universes visited: {}", *nodes_visited);
-            }
-            best_moves.choose(&mut rand::thread_rng()).unwrap()
+        fn get_color(&self) -> CheckersColor {
+            self.color
         }
 
-        fn choose_capture<'a>(&'a self, captures: &'a [JumpChain], board: Board) -> &JumpChain {
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                *nodes_visited = 0;
-            }
-
-            if captures.len() == 1 {
-                return captures.first().unwrap()
-            }
-
-            let captures_eval: Vec<(usize, f64)> = captures
-                .par_iter()
-                .enumerate()
-                .map(|(i, capture)| {
-                    let mut controller = CheckersController::new(board);
-                    controller.execute_capture(capture);
-                    let eval = self.minmax(&controller, self.depth - 1, self.get_color().opposite());
-                    (i, eval)
-                })
-                .collect();
+        fn set_color(&mut self, color: CheckersColor) {
+            self.color = color;
+        }
 
-            let best_eval = self.get_best_eval(&captures_eval);
-            let indices = self.get_best_indices(&captures_eval, best_eval);
-            let best_captures = self.get_at_indices(&indices, captures);
+        fn name(&self) -> String {
+            format!("MinMaxBot(depth={})", self.engine.depth())
+        }
 
-            println!("{:?} MinMaxBot best: {}",self.color , best_eval * if self.color.is_white() {1.0} else {-1.0});
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                println!("Universes visited: {}", *nodes_visited);
-            }
-            best_captures.choose(&mut rand::thread_rng()).unwrap()
+        fn reseed(&mut self, seed: u64) {
+            self.rng = StdRng::seed_from_u64(seed);
         }
 
-        fn get_color(&self) -> CheckersColor {
-            self.color
+        fn last_evaluation(&self) -> Option<f64> {
+            self.last_evaluation
         }
 
-        fn set_color(&mut self, color: CheckersColor) {
-            self.color = color;
+        fn last_search_stats(&self) -> Option<SearchStats> {
+            self.last_stats
         }
     }
 
     pub struct AlphaBetaBot<T> {
-        estimator: T,
-        depth: usize,
+        engine: SearchEngine<T>,
         color: CheckersColor,
-        nodes_visited: Arc<Mutex<usize>>
+        last_stats: Option<SearchStats>,
+        last_evaluation: Option<f64>,
+        rng: StdRng,
+        opening_book: Option<Arc<OpeningBook>>,
+        skill_level: SkillLevel,
+        pondering: bool,
+        /// Search running while the opponent thinks, stopped when it is the bot's turn again.
+        ponder: Option<Ponder>,
+        ponder_pool: Option<ThreadPool>,
     }
 
     impl <T> AlphaBetaBot<T> {
-        pub const MIN_SCORE: f64 = -1e10;
-        pub const MAX_SCORE: f64 = 1e10;
-
         pub fn new(estimator: T, depth: usize) -> Self {
-            Self{estimator, depth, color: CheckersColor::White, nodes_visited: Arc::new(Mutex::new(0))}
-        }
-
-        fn get_best_eval(&self, evals: &Vec<(usize, f64)>) -> f64 {
-            if self.color.is_white() {
-                evals
-                    .iter()
-                    .map(|(_, eval)| eval)
-                    .cloned()
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(f64::MIN)
-            } else {
-                evals
-                    .iter()
-                    .map(|(_, eval)| eval)
-                    .cloned()
-                    .min_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(f64::MAX)
+            Self{engine: SearchEngine::new(estimator, depth), color: CheckersColor::White, last_stats: None, last_evaluation: None, rng: StdRng::from_entropy(), opening_book: None, skill_level: SkillLevel::Master, pondering: false, ponder: None, ponder_pool: None}
+        }
+
+        pub fn with_skill_level(mut self, level: SkillLevel) -> Self {
+            //! `SkillLevel::Master` by default. Weaker levels search shallower, sample among all actions
+            //! and ignore the opening book. They need exact scores of every root action, so the root
+            //! actions are searched independently.
+            if let Some(depth) = level.max_depth() {
+                let depth = depth.min(self.engine.depth());
+                self.engine = self.engine.with_depth(depth);
+            }
+            if level.needs_all_scores() {
+                self.engine = self.engine.with_root_search(RootSearch::Independent);
             }
+            self.skill_level = level;
+            self
         }
 
-        fn get_best_indices(&self, evals: &Vec<(usize, f64)>, best: f64) -> Vec<usize> {
-            evals
-                .iter()
-                .filter(|(i, eval)| (eval - best).abs() < f64::EPSILON)
-                .map(|(i, _)| *i)
-                .collect()
+        pub fn with_opening_book(mut self, book: Arc<OpeningBook>) -> Self {
+            //! The book is consulted before searching, a book hit is played without any search.
+            self.opening_book = Some(book);
+            self
         }
 
-        fn get_at_indices<'a, U>(&'a self, indices: &[usize], values: &'a [U]) -> Vec<&U> {
-            let mut ret = Vec::with_capacity(indices.len());
-            for &i in indices {
-                ret.push(&values[i])
-            }
-            ret
+        pub fn with_tablebase(mut self, tablebase: Arc<Tablebase>) -> Self {
+            //! Positions covered by the tablebase are scored from it instead of being searched.
+            self.engine = self.engine.with_tablebase(Some(tablebase));
+            self
         }
 
-        fn update_estimate(est: f64, current: f64, alpha: f64, beta: f64, cut: f64, color: CheckersColor) -> (f64, f64, bool) {
-            //! (new estimate, new cut, do break)
-            let mut ret_current = current;
-            let mut ret_cut = cut;
-            let mut ret_break = false;
-            if color.is_white() {
-                if est > current {
-                    ret_current = est;
-                }
-                if est > cut {
-                    ret_cut = est;
-                }
-                if beta <= cut {
-                    ret_break = true;
-                }
-            } else {
-                if est < current {
-                    ret_current = est;
-                }
-                if est < cut {
-                    ret_cut = est;
-                }
-                if cut < alpha {
-                    ret_break = true;
-                }
-            }
-            (ret_current, ret_cut, ret_break)
-        }
-    }
-
-    impl <T: BoardEstimator> AlphaBetaBot<T> {
-        fn minmax(
-            &self,
-            controller: &CheckersController,
-            depth: usize,
-            current_color: CheckersColor,
-            alpha: f64,
-            beta: f64
-        ) -> f64 {
-            if depth == 0 {
-                {
-                    let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                    *nodes_visited += 1;
-                }
-                return self.estimator.score(&controller.board);
-            }
-            let idle_moves = match self.color {
-                CheckersColor::White => controller.get_white_queen_idle_moves(),
-                CheckersColor::Black => controller.get_black_queen_idle_moves(),
-            };
-            if idle_moves > 8 {
-                return if current_color.is_white() { Self::MIN_SCORE } else { Self::MAX_SCORE };
-            }
-            let (jumps, moves) = controller.options(current_color);
-            if !jumps.is_empty() {
-                return self.minmax_jumps(controller, &jumps, depth, current_color, alpha, beta);
-            }
-            if !moves.is_empty() {
-                return self.minmax_moves(controller, &moves, depth, current_color, alpha, beta);
-            }
-            if current_color.is_white() { Self::MIN_SCORE } else { Self::MAX_SCORE }
-        }
-
-        fn minmax_jumps(
-            &self,
-            controller: &CheckersController,
-            captures: &[JumpChain],
-            depth: usize,
-            current_color: CheckersColor,
-            alpha: f64,
-            beta: f64
-        ) -> f64 {
-            let mut current = if current_color.is_white() { f64::MIN } else { f64::MAX };
-            let mut new_cut = current;
-            let mut do_break = false;
-            for capture in captures {
-                let mut controller = CheckersController::with_idle_moves(
-                    controller.board,
-                    controller.get_white_queen_idle_moves(),
-                    controller.get_black_queen_idle_moves()
-                );
-                controller.execute_capture(capture);
-                let est = self.minmax(
-                    &controller,
-                    depth - 1,
-                    current_color.opposite(),
-                    if current_color.is_white() { new_cut } else {alpha},
-                    if current_color.is_white() {beta} else {new_cut}
-                );
-                (current, new_cut, do_break) = Self::update_estimate(est, current, alpha, beta, new_cut, current_color);
-                if do_break {
-                    break
-                }
-                // if current_color.is_white() {
-                //     if est > current {
-                //         current = est;
-                //     }
-                //     if est > new_cut {
-                //         new_cut = est;
-                //     }
-                //     if beta <= new_cut {
-                //         break
-                //     }
-                // } else {
-                //     if est < current {
-                //         current = est;
-                //     }
-                //     if est < new_cut {
-                //         new_cut = est;
-                //     }
-                //     if new_cut < alpha {
-                //         break;
-                //     }
-                // }
-            }
-            current
-        }
-
-        fn minmax_moves(
-            &self,
-            controller: &CheckersController,
-            moves: &[Move],
-            depth: usize,
-            current_color: CheckersColor,
-            alpha: f64,
-            beta: f64
-        ) -> f64 {
-            let mut current = if current_color.is_white() { f64::MIN } else { f64::MAX };
-            let mut new_cut = current;
-            let mut do_break = false;
-            for move_ in moves {
-                let mut controller = CheckersController::with_idle_moves(
-                    controller.board,
-                    controller.get_white_queen_idle_moves(),
-                    controller.get_black_queen_idle_moves(),
-                );
-                controller.execute_move(move_);
-                let est = self.minmax(
-                    &controller,
-                    depth - 1,
-                    current_color.opposite(),
-                    if current_color.is_white() { new_cut } else {alpha},
-                    if current_color.is_white() {beta} else {new_cut}
-                );
-                (current, new_cut, do_break) = Self::update_estimate(est, current, alpha, beta, new_cut, current_color);
-                if do_break {
-                    break;
-                }
-                // if current_color.is_white() {
-                //     if est > current {
-                //         current = est;
-                //     }
-                //     if est > new_cut {
-                //         new_cut = est;
-                //     }
-                //     if beta <= new_cut {
-                //         break
-                //     }
-                // } else {
-                //     if est < current {
-                //         current = est;
-                //     }
-                //     if est < new_cut {
-                //         new_cut = est;
-                //     }
-                //     if new_cut < alpha {
-                //         break;
-                //     }
-                // }
-            }
-            current
+        pub fn with_pvs(mut self, enabled: bool) -> Self {
+            //! Principal variation search, enabled by default.
+            self.engine = self.engine.with_pvs(enabled);
+            self
         }
-    }
 
-    impl <T: BoardEstimator + Sync> Player for AlphaBetaBot<T> {
-        fn choose_move<'a>(&'a self, moves: &'a [Move], board: Board) -> &Move {
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                *nodes_visited = 0;
-            }
-            if moves.len() == 1 {
-                return moves.first().unwrap()
-            }
+        pub fn with_aspiration_window(mut self, window: Option<f64>) -> Self {
+            //! Half width of the aspiration window around the score of a search two plies shallower, `None` (the
+            //! default) searches once with the full window.
+            self.engine = self.engine.with_aspiration_window(window);
+            self
+        }
 
-            let moves_eval: Vec<(usize, f64)> = moves
-                .par_iter()
-                .enumerate()
-                .map(|(i, move_)| {
-                    let mut controller = CheckersController::new(board);
-                    controller.execute_move(move_);
-                    let eval = self.minmax(&controller, self.depth - 1, self.get_color().opposite(), Self::MIN_SCORE - 1.0, Self::MAX_SCORE + 1.0);
-                    (i, eval)
-                }).collect();
-            let best_eval = self.get_best_eval(&moves_eval);
-            let indices = self.get_best_indices(&moves_eval, best_eval);
-            let best_moves = self.get_at_indices(&indices, moves);
-
-            println!("{:?} AlphaBetaBot best: {}", self.color, best_eval * if self.color.is_white() {1.0} else {-1.0});
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                println!("Universes visited: {}", *nodes_visited);
-            }
-            best_moves.choose(&mut rand::thread_rng()).unwrap()
+        pub fn with_transposition_table(mut self, table: Option<Arc<TranspositionTable>>) -> Self {
+            //! Table shared by all search threads and kept between moves, by default one with
+            //! `TranspositionTable::DEFAULT_ENTRIES` entries. `None` searches without one.
+            self.engine = self.engine.with_transposition_table(table);
+            self
         }
 
-        fn choose_capture<'a>(&'a self, captures: &'a [JumpChain], board: Board) -> &JumpChain {
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                *nodes_visited = 0;
-            }
+        pub fn with_root_search(mut self, root_search: RootSearch) -> Self {
+            //! Young Brothers Wait by default.
+            self.engine = self.engine.with_root_search(root_search);
+            self
+        }
 
-            if captures.len() == 1 {
-                return captures.first().unwrap()
-            }
+        pub fn with_pondering(mut self, enabled: bool) -> Self {
+            //! After every move the position after the opponent's expected reply is searched in the background until
+            //! the bot has to move again, filling the transposition table. Off by default and meant for games against
+            //! humans, against another bot both searches compete for the same cores. Needs the transposition table.
+            self.pondering = enabled;
+            self
+        }
 
-            let captures_eval: Vec<(usize, f64)> = captures
-                .par_iter()
-                .enumerate()
-                .map(|(i, capture)| {
-                    let mut controller = CheckersController::new(board);
-                    controller.execute_capture(capture);
-                    let eval = self.minmax(&controller, self.depth - 1, self.get_color().opposite(), Self::MIN_SCORE - 1.0, Self::MAX_SCORE + 1.0);
-                    (i, eval)
-                })
-                .collect();
+        fn book_choice(&mut self, notations: Vec<String>, board: &Board) -> Option<usize> {
+            let book = self.opening_book.as_ref()?;
+            book.choose(board, self.color, &notations, &mut self.rng)
+        }
+    }
 
-            let best_eval = self.get_best_eval(&captures_eval);
-            let indices = self.get_best_indices(&captures_eval, best_eval);
-            let best_captures = self.get_at_indices(&indices, captures);
+    impl <T: BoardEstimator + Sync + Send + 'static> AlphaBetaBot<T> {
+        pub fn evaluate(&self, controller: &CheckersController, current_color: CheckersColor) -> f64 {
+            //! Searches the position with `current_color` to move and returns its score from white's point of view.
+            self.engine.evaluate(controller, current_color)
+        }
 
-            println!("{:?} AlphaBetaBot best: {}",self.color , best_eval * if self.color.is_white() {1.0} else {-1.0});
-            {
-                let mut nodes_visited = self.nodes_visited.lock().unwrap();
-                println!("Universes visited: {}", *nodes_visited);
+        fn choose(&mut self, actions: &[CheckersAction], notations: Vec<String>, controller: &CheckersController) -> usize {
+            self.last_evaluation = None;
+            self.last_stats = None;
+            let ponder_hit = self.ponder.take().is_some_and(|ponder| ponder.finish(controller));
+            if actions.len() == 1 {
+                return 0;
+            }
+            if !self.skill_level.needs_all_scores() {
+                if let Some(i) = self.book_choice(notations, &controller.board) {
+                    return i;
+                }
             }
-            best_captures.choose(&mut rand::thread_rng()).unwrap()
+            let (scores, mut stats) = self.engine.score_actions(controller, actions, self.get_color());
+            let (i, best_eval) = self.skill_level.choose(&scores, self.color, &mut self.rng);
+            stats.ponder_hits = ponder_hit as u64;
+            self.last_evaluation = Some(best_eval);
+            self.last_stats = Some(stats);
+            if self.pondering {
+                let mut after = controller.clone();
+                after.play(&actions[i]);
+                let pool = self.ponder_pool.get_or_insert_with(Ponder::pool);
+                self.ponder = Ponder::start(&self.engine, pool, &after, self.color);
+            }
+            i
+        }
+    }
+
+    impl <T: BoardEstimator + Sync + Send + 'static> Player for AlphaBetaBot<T> {
+        fn choose_move<'a>(&mut self, moves: &'a [Move], controller: &CheckersController) -> &'a Move {
+            let actions: Vec<CheckersAction> = moves.iter().map(|&move_| CheckersAction::Move(move_)).collect();
+            &moves[self.choose(&actions, moves.iter().map(move_notation).collect(), controller)]
+        }
+
+        fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], controller: &CheckersController) -> &'a JumpChain {
+            let actions: Vec<CheckersAction> = captures.iter().cloned().map(CheckersAction::JumpChain).collect();
+            &captures[self.choose(&actions, captures.iter().map(capture_notation).collect(), controller)]
         }
 
         fn get_color(&self) -> CheckersColor {
@@ -606,18 +357,126 @@ pub mod player {
         fn set_color(&mut self, color: CheckersColor) {
             self.color = color;
         }
+
+        fn name(&self) -> String {
+            match self.skill_level {
+                SkillLevel::Master => format!("AlphaBetaBot(depth={})", self.engine.depth()),
+                level => format!("AlphaBetaBot(depth={}, {:?})", self.engine.depth(), level),
+            }
+        }
+
+        fn reseed(&mut self, seed: u64) {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+
+        fn last_evaluation(&self) -> Option<f64> {
+            self.last_evaluation
+        }
+
+        fn last_search_stats(&self) -> Option<SearchStats> {
+            self.last_stats
+        }
     }
 }
 
 pub mod estimators {
+    use std::fmt::{Display, Formatter};
+    use serde::{Deserialize, Serialize};
     use crate::board::Board;
     use crate::controller::{CheckersColor, CheckersController};
+    use crate::tune::Tunable;
+
+    /// What estimators score: a board with the side to move and the idle queen move counters.
+    #[derive(Copy, Clone, Debug)]
+    pub struct Position {
+        pub board: Board,
+        pub side_to_move: CheckersColor,
+        pub white_queen_idle_moves: u8,
+        pub black_queen_idle_moves: u8,
+    }
+
+    impl Position {
+        pub fn new(controller: &CheckersController, side_to_move: CheckersColor) -> Self {
+            Self {
+                board: controller.board,
+                side_to_move,
+                white_queen_idle_moves: controller.get_white_queen_idle_moves(),
+                black_queen_idle_moves: controller.get_black_queen_idle_moves(),
+            }
+        }
+
+        pub fn from_board(board: Board, side_to_move: CheckersColor) -> Self {
+            //! A position without idle queen moves.
+            Self { board, side_to_move, white_queen_idle_moves: 0, black_queen_idle_moves: 0 }
+        }
+
+        pub fn idle_moves(&self, color: CheckersColor) -> u8 {
+            match color {
+                CheckersColor::White => self.white_queen_idle_moves,
+                CheckersColor::Black => self.black_queen_idle_moves,
+            }
+        }
+    }
+
+    pub fn phase(board: &Board) -> f64 {
+        //! Game phase from 1 with all 24 pieces on the board to 0 with none.
+        (board.num_figures(CheckersColor::White) + board.num_figures(CheckersColor::Black)) as f64 / 24.0
+    }
 
     pub trait BoardEstimator {
-        fn score(&self, board: &Board) -> f64;
+        /// Score from white's point of view, whoever is to move.
+        fn score(&self, position: &Position) -> f64;
+
+        fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+            //! The terms the score is made of, their `ScoreTerm::score`s add up to `score`.
+            //! Estimators without terms report the whole score as one.
+            vec![ScoreTerm { name: "score".into(), white: self.score(position), black: 0.0, weight: 1.0 }]
+        }
     }
 
-    #[derive(Copy, Clone)]
+    impl <T: BoardEstimator + ?Sized> BoardEstimator for Box<T> {
+        fn score(&self, position: &Position) -> f64 {
+            (**self).score(position)
+        }
+
+        fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+            (**self).explain(position)
+        }
+    }
+
+    /// One term of a score: the values of both sides and the weight applied to their difference.
+    #[derive(Clone, Debug)]
+    pub struct ScoreTerm {
+        pub name: String,
+        pub white: f64,
+        pub black: f64,
+        pub weight: f64,
+    }
+
+    impl ScoreTerm {
+        pub fn score(&self) -> f64 {
+            //! Contribution to the score from white's point of view.
+            self.weight * (self.white - self.black)
+        }
+    }
+
+    /// Table of the terms of a score with their total.
+    pub struct Explanation(pub Vec<ScoreTerm>);
+
+    impl Display for Explanation {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "{:<24} {:>8} {:>8} {:>8} {:>8}", "term", "white", "black", "weight", "score")?;
+            for term in &self.0 {
+                // adding zero turns -0.0 into 0.0
+                let score = term.score() + 0.0;
+                writeln!(f, "{:<24} {:>8.2} {:>8.2} {:>8.3} {:>8.3}", term.name, term.white, term.black, term.weight, score)?;
+            }
+            write!(f, "{:<24} {:>35.3}", "total", self.0.iter().map(ScoreTerm::score).sum::<f64>())
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
     pub struct CountEstimator {
         pawn_weight: f64,
         queen_weight: f64
@@ -629,7 +488,8 @@ pub mod estimators {
     }
 
     impl BoardEstimator for CountEstimator {
-        fn score(&self, board: &Board) -> f64 {
+        fn score(&self, position: &Position) -> f64 {
+            let board = &position.board;
             let pawns = board.num_pawns(CheckersColor::White);
             let queens = board.num_queens(CheckersColor::White);
             let enemy_pawns = board.num_pawns(CheckersColor::Black);
@@ -638,22 +498,80 @@ pub mod estimators {
             let negative = enemy_pawns as f64 * self.pawn_weight + enemy_queens as f64 * self.queen_weight;
             positive - negative
         }
+
+        fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+            let board = &position.board;
+            vec![
+                ScoreTerm {
+                    name: "pawns".into(),
+                    white: board.num_pawns(CheckersColor::White) as f64,
+                    black: board.num_pawns(CheckersColor::Black) as f64,
+                    weight: self.pawn_weight,
+                },
+                ScoreTerm {
+                    name: "queens".into(),
+                    white: board.num_queens(CheckersColor::White) as f64,
+                    black: board.num_queens(CheckersColor::Black) as f64,
+                    weight: self.queen_weight,
+                },
+            ]
+        }
     }
 
+    impl Tunable for CountEstimator {
+        fn parameters(&self) -> Vec<f64> {
+            vec![self.pawn_weight, self.queen_weight]
+        }
+
+        fn with_parameters(&self, parameters: &[f64]) -> Self {
+            Self::new(parameters[0], parameters[1])
+        }
+    }
+
+    /// Square weights indexed `[x][y]`. `board_weights` are white's, black uses its own table if one is given
+    /// and otherwise white's mirrored to its side of the board, i.e. the weight of `(7 - x, 7 - y)`.
+    #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
     pub struct WeightMatrixEstimator {
         board_weights: [[f64; 8]; 8],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        black_weights: Option<[[f64; 8]; 8]>,
         pawn_weight: f64,
         queen_weight: f64
     }
 
     impl WeightMatrixEstimator {
         pub fn new(board_weights: [[f64; 8]; 8], pawn_weight: f64, queen_weight: f64) -> Self {
-            Self { board_weights, pawn_weight, queen_weight }
+            Self { board_weights, black_weights: None, pawn_weight, queen_weight }
+        }
+
+        pub fn with_black_weights(mut self, black_weights: Option<[[f64; 8]; 8]>) -> Self {
+            //! Black's own square weights, `None` (the default) mirrors white's.
+            self.black_weights = black_weights;
+            self
+        }
+
+        pub fn board_weights(&self) -> &[[f64; 8]; 8] {
+            &self.board_weights
+        }
+
+        pub fn black_weights(&self) -> Option<&[[f64; 8]; 8]> {
+            self.black_weights.as_ref()
+        }
+
+        fn square_weight(&self, color: CheckersColor, x: u8, y: u8) -> f64 {
+            let (x, y) = (x as usize, y as usize);
+            match (color, &self.black_weights) {
+                (CheckersColor::White, _) => self.board_weights[x][y],
+                (CheckersColor::Black, Some(black_weights)) => black_weights[x][y],
+                (CheckersColor::Black, None) => self.board_weights[7 - x][7 - y],
+            }
         }
     }
 
     impl BoardEstimator for WeightMatrixEstimator {
-        fn score(&self, board: &Board) -> f64 {
+        fn score(&self, position: &Position) -> f64 {
+            let board = &position.board;
             let controller = CheckersController::new(*board);
             let white_positions = controller.get_white_pieces_position();
             let black_positions = controller.get_black_pieces_position();
@@ -675,51 +593,391 @@ pub mod estimators {
                 let figure = board.at(x, y);
                 if let Some(figure) = figure {
                     if figure.is_queen() {
-                        black_score += self.board_weights[x as usize][y as usize] * self.queen_weight;
+                        black_score += self.square_weight(CheckersColor::Black, x, y) * self.queen_weight;
                     } else {
-                        black_score += self.board_weights[x as usize][y as usize] * self.pawn_weight;
+                        black_score += self.square_weight(CheckersColor::Black, x, y) * self.pawn_weight;
                     }
                 }
             }
             white_score - black_score
         }
+
+        fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+            //! Pawns and queens each counted with the weights of the squares they stand on.
+            let board = &position.board;
+            let mut pawns = ScoreTerm { name: "pawns".into(), white: 0.0, black: 0.0, weight: self.pawn_weight };
+            let mut queens = ScoreTerm { name: "queens".into(), white: 0.0, black: 0.0, weight: self.queen_weight };
+            for y in 0..8 {
+                for x in 0..8 {
+                    let Some(figure) = board.at(x, y) else { continue };
+                    let term = if figure.is_queen() { &mut queens } else { &mut pawns };
+                    let value = if figure.is_white() { &mut term.white } else { &mut term.black };
+                    *value += self.square_weight(figure.color(), x, y);
+                }
+            }
+            vec![pawns, queens]
+        }
+    }
+
+    impl Tunable for WeightMatrixEstimator {
+        fn parameters(&self) -> Vec<f64> {
+            //! Pawn and queen weight followed by white's weights of the 32 dark squares, the others are never used.
+            //! Separate black weights are not tuned.
+            let mut parameters = vec![self.pawn_weight, self.queen_weight];
+            for (x, column) in self.board_weights.iter().enumerate() {
+                parameters.extend(column.iter().skip(x % 2).step_by(2));
+            }
+            parameters
+        }
+
+        fn with_parameters(&self, parameters: &[f64]) -> Self {
+            let mut board_weights = self.board_weights;
+            let mut squares = parameters[2..].iter();
+            for (x, column) in board_weights.iter_mut().enumerate() {
+                for weight in column.iter_mut().skip(x % 2).step_by(2) {
+                    *weight = *squares.next().expect("one parameter per dark square");
+                }
+            }
+            Self::new(board_weights, parameters[0], parameters[1]).with_black_weights(self.black_weights)
+        }
+    }
+
+    /// Number of legal actions of white minus those of black.
+    #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+    pub struct MobilityEstimator;
+
+    impl BoardEstimator for MobilityEstimator {
+        fn score(&self, position: &Position) -> f64 {
+            self.explain(position)[0].score()
+        }
+
+        fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+            let controller = CheckersController::new(position.board);
+            let actions = |color| {
+                let (captures, moves) = controller.options(color);
+                (captures.len() + moves.len()) as f64
+            };
+            vec![ScoreTerm {
+                name: "mobility".into(),
+                white: actions(CheckersColor::White),
+                black: actions(CheckersColor::Black),
+                weight: 1.0,
+            }]
+        }
+    }
+
+    impl Tunable for MobilityEstimator {
+        fn parameters(&self) -> Vec<f64> {
+            Vec::new()
+        }
+
+        fn with_parameters(&self, _parameters: &[f64]) -> Self {
+            *self
+        }
+    }
+
+    /// Weights of the `PositionalEstimator` terms before phase scaling.
+    #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct PositionalWeights {
+        pub pawn: f64,
+        pub queen: f64,
+        /// Per legal action.
+        pub mobility: f64,
+        /// Per pawn on the own back rank, scaled by the phase.
+        pub back_rank: f64,
+        /// Per piece on the 16 center squares, scaled by the phase.
+        pub center: f64,
+        /// Per pawn no enemy piece can stop from promoting, scaled by one minus the phase.
+        pub runaway: f64,
+        /// Per row the pawns have advanced, scaled by one minus the phase.
+        pub tempo: f64,
+        /// Per queen without any action.
+        pub trapped_queen: f64,
+        /// Per piece the opponent, being the side to move, could capture right away.
+        pub exposed: f64,
+        /// Per idle queen move, more than 8 of them lose the game.
+        pub idle_moves: f64,
+    }
+
+    impl Default for PositionalWeights {
+        fn default() -> Self {
+            Self {
+                pawn: 1.0,
+                queen: 3.0,
+                mobility: 0.05,
+                back_rank: 0.2,
+                center: 0.1,
+                runaway: 0.5,
+                tempo: 0.05,
+                trapped_queen: -1.0,
+                exposed: -0.3,
+                idle_moves: -0.1,
+            }
+        }
+    }
+
+    /// Material plus checkers-specific positional terms. The phase goes from 1 with all 24 pieces on the board
+    /// to 0 with none, opening terms are scaled by it and endgame terms by one minus it.
+    #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct PositionalEstimator {
+        weights: PositionalWeights,
+    }
+
+    impl PositionalEstimator {
+        pub fn new(weights: PositionalWeights) -> Self {
+            Self { weights }
+        }
+
+        fn is_runaway(board: &Board, x: u8, y: u8, color: CheckersColor) -> bool {
+            //! No enemy piece in the cone in front of the pawn and a free square to move to.
+            let direction: i8 = if color.is_white() { 1 } else { -1 };
+            let mut free_step = false;
+            for distance in 1..8i8 {
+                let row = y as i8 + direction * distance;
+                if !(0..8).contains(&row) {
+                    break;
+                }
+                for column in (x as i8 - distance).max(0)..=(x as i8 + distance).min(7) {
+                    match board.at(column as u8, row as u8) {
+                        Some(figure) if figure.color() != color => return false,
+                        None if distance == 1 && (column - x as i8).abs() == 1 => free_step = true,
+                        _ => {}
+                    }
+                }
+            }
+            free_step
+        }
+
+        fn side_terms(&self, position: &Position, controller: &CheckersController, color: CheckersColor) -> [f64; 10] {
+            //! Unweighted values of all terms for one side, in the order of `explain`.
+            let board = &position.board;
+            let positions = match color {
+                CheckersColor::White => controller.get_white_pieces_position(),
+                CheckersColor::Black => controller.get_black_pieces_position(),
+            };
+            let (captures, moves) = controller.options(color);
+            let mut values = [0.0; 10];
+            values[2] = (captures.len() + moves.len()) as f64;
+            for &(x, y) in &positions {
+                let Some(figure) = board.at(x, y) else { continue };
+                let row = if color.is_white() { y } else { 7 - y };
+                if figure.is_queen() {
+                    values[1] += 1.0;
+                    if !controller.can_move(x, y) && !controller.can_capture(x, y) {
+                        values[7] += 1.0;
+                    }
+                } else {
+                    values[0] += 1.0;
+                    values[6] += row as f64;
+                    if row == 0 {
+                        values[3] += 1.0;
+                    }
+                    if Self::is_runaway(board, x, y, color) {
+                        values[5] += 1.0;
+                    }
+                }
+                if (2..=5).contains(&x) && (2..=5).contains(&y) {
+                    values[4] += 1.0;
+                }
+            }
+            // pieces of this side the opponent can jump over now, counted once however many jumps reach them,
+            // the side to move can still protect its own
+            let enemies = match color {
+                _ if position.side_to_move == color => Vec::new(),
+                CheckersColor::White => controller.get_black_pieces_position(),
+                CheckersColor::Black => controller.get_white_pieces_position(),
+            };
+            let mut exposed: Vec<(u8, u8)> = enemies.iter()
+                .flat_map(|&(x, y)| controller.possible_captures_at(x, y))
+                .map(|jump| jump.over_position())
+                .collect();
+            exposed.sort_unstable();
+            exposed.dedup();
+            values[8] = exposed.len() as f64;
+            values[9] = position.idle_moves(color) as f64;
+            values
+        }
+    }
+
+    impl BoardEstimator for PositionalEstimator {
+        fn score(&self, position: &Position) -> f64 {
+            self.explain(position).iter().map(ScoreTerm::score).sum()
+        }
+
+        fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+            //! Weights are phase scaled.
+            let board = &position.board;
+            let controller = CheckersController::new(*board);
+            let white = self.side_terms(position, &controller, CheckersColor::White);
+            let black = self.side_terms(position, &controller, CheckersColor::Black);
+            let phase = phase(board);
+            let w = &self.weights;
+            let weighted = [
+                ("pawns", w.pawn),
+                ("queens", w.queen),
+                ("mobility", w.mobility),
+                ("back rank", w.back_rank * phase),
+                ("center", w.center * phase),
+                ("runaway pawns", w.runaway * (1.0 - phase)),
+                ("tempo", w.tempo * (1.0 - phase)),
+                ("trapped queens", w.trapped_queen),
+                ("exposed pieces", w.exposed),
+                ("idle moves", w.idle_moves),
+            ];
+            weighted.iter().enumerate()
+                .map(|(i, &(name, weight))| ScoreTerm { name: name.into(), white: white[i], black: black[i], weight })
+                .collect()
+        }
+    }
+
+    impl Tunable for PositionalEstimator {
+        fn parameters(&self) -> Vec<f64> {
+            let w = &self.weights;
+            vec![
+                w.pawn, w.queen, w.mobility, w.back_rank, w.center, w.runaway, w.tempo, w.trapped_queen, w.exposed,
+                w.idle_moves,
+            ]
+        }
+
+        fn with_parameters(&self, parameters: &[f64]) -> Self {
+            let &[pawn, queen, mobility, back_rank, center, runaway, tempo, trapped_queen, exposed, idle_moves] = parameters else {
+                panic!("expected 10 positional weights, got {}", parameters.len());
+            };
+            Self::new(PositionalWeights {
+                pawn, queen, mobility, back_rank, center, runaway, tempo, trapped_queen, exposed, idle_moves,
+            })
+        }
     }
 }
 
 
-pub struct Game<WP, BP> {
+pub struct Game {
     controller: CheckersController,
-    white_player : WP,
-    black_player: BP,
+    white_player: Box<dyn Player>,
+    black_player: Box<dyn Player>,
     current_player: CheckersColor,
+    record: GameRecord,
+    move_limit: Option<usize>,
+    observer: Box<dyn GameObserver>,
+    started: bool,
 }
 
 
-impl <WP: Player, BP: Player> Game<WP, BP> {
-    pub fn new(controller: CheckersController, mut white_player: WP, mut black_player: BP) -> Self {
+impl Game {
+    pub fn new(controller: CheckersController, mut white_player: Box<dyn Player>, mut black_player: Box<dyn Player>) -> Self {
         white_player.set_color(CheckersColor::White);
         black_player.set_color(CheckersColor::Black);
-        Self {
+        let record = GameRecord::new(white_player.name(), black_player.name(), &controller);
+        let mut game = Self {
             controller,
             white_player,
             black_player,
-            current_player: CheckersColor::White
+            current_player: CheckersColor::White,
+            record,
+            move_limit: None,
+            observer: Box::new(ConsoleObserver),
+            started: false,
+        };
+        game.set_seed(rand::random());
+        game
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        //! Reseeds both players from `seed` and stores it in the record, so the game can be reproduced
+        //! by playing it again with the same players and seed.
+        self.record.seed = Some(seed);
+        self.white_player.reseed(derive_seed(seed, 0));
+        self.black_player.reseed(derive_seed(seed, 1));
+    }
+
+    pub fn player(&self, color: CheckersColor) -> &dyn Player {
+        match color {
+            CheckersColor::White => self.white_player.as_ref(),
+            CheckersColor::Black => self.black_player.as_ref(),
         }
     }
 
-    pub fn run(&mut self) -> CheckersColor {
-        println!("{}", self.controller.board);
-        while let Some(_) = self.step() {
-            self.controller.promote();
-            println!("{}", self.controller.board);
+    fn player_mut(&mut self, color: CheckersColor) -> &mut dyn Player {
+        match color {
+            CheckersColor::White => self.white_player.as_mut(),
+            CheckersColor::Black => self.black_player.as_mut(),
+        }
+    }
+
+    pub fn set_player(&mut self, color: CheckersColor, mut player: Box<dyn Player>) -> Box<dyn Player> {
+        //! Replaces the player of the given color (also in the middle of a game) and returns the previous one.
+        //! The record names both, e.g. `HumanPlayer, then AlphaBetaBot`.
+        player.set_color(color);
+        if let Some(seed) = self.record.seed {
+            player.reseed(derive_seed(seed, self.record.len() as u64 + 2));
+        }
+        let name = match color {
+            CheckersColor::White => &mut self.record.white,
+            CheckersColor::Black => &mut self.record.black,
+        };
+        *name = if self.record.plies.is_empty() { player.name() } else { format!("{name}, then {}", player.name()) };
+        match color {
+            CheckersColor::White => std::mem::replace(&mut self.white_player, player),
+            CheckersColor::Black => std::mem::replace(&mut self.black_player, player),
+        }
+    }
+
+    pub fn set_move_limit(&mut self, plies: usize) {
+        //! Declares a draw once the given number of plies has been played.
+        self.move_limit = Some(plies);
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn GameObserver>) {
+        //! Replaces the observer notified about the game, `ConsoleObserver` by default.
+        self.observer = observer;
+    }
+
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub fn into_record(self) -> GameRecord {
+        self.record
+    }
+
+    pub fn run(&mut self) -> GameOutcome {
+        self.run_for(usize::MAX).expect("the game should end")
+    }
+
+    pub fn run_for(&mut self, plies: usize) -> Option<GameOutcome> {
+        //! Plays at most `plies` more plies and returns the outcome once the game is over, so players can be
+        //! replaced with `set_player` in between.
+        if !self.started {
+            self.started = true;
+            self.observer.game_started(&self.record.white, &self.record.black, &self.controller.board);
+        }
+        if let Some(outcome) = self.record.outcome {
+            return Some(outcome);
+        }
+        for _ in 0..plies {
+            if self.step().is_none() {
+                return self.record.outcome;
+            }
             if self.controller.board.num_white_figures() == 0 {
-                return CheckersColor::Black;
+                return Some(self.finish(Some(CheckersColor::Black), EndReason::NoPieces));
             }
             if self.controller.board.num_black_figures() == 0 {
-                return CheckersColor::White;
+                return Some(self.finish(Some(CheckersColor::White), EndReason::NoPieces));
+            }
+            if self.move_limit.is_some_and(|limit| self.record.len() >= limit) {
+                return Some(self.finish(None, EndReason::MoveLimit));
             }
         }
-        self.current_player.opposite()
+        None
+    }
+
+    fn finish(&mut self, winner: Option<CheckersColor>, reason: EndReason) -> GameOutcome {
+        let outcome = GameOutcome { winner, reason };
+        self.record.outcome = Some(outcome);
+        self.observer.game_over(&outcome);
+        outcome
     }
 
     pub fn step(&mut self) -> Option<()> {
@@ -728,28 +986,132 @@ impl <WP: Player, BP: Player> Game<WP, BP> {
             CheckersColor::Black => self.controller.get_black_queen_idle_moves(),
         };
         if idle_moves > 8 {
+            self.finish(Some(self.current_player.opposite()), EndReason::IdleMoves);
             return None
         }
+        let controller = self.controller.clone();
         let (captures, moves) = self.controller.options(self.current_player);
-        if !captures.is_empty() {
-            let capture = match self.current_player {
-                CheckersColor::White => self.white_player.choose_capture(&captures, self.controller.board),
-                CheckersColor::Black => self.black_player.choose_capture(&captures, self.controller.board),
-            };
-            self.controller.execute_capture(capture);
-            self.controller.board.flags = 0;
-            self.current_player = self.current_player.opposite();
-            return Some(());
-        }
-        if !moves.is_empty() {
-            let move_ = match self.current_player {
-                CheckersColor::White => self.white_player.choose_move(&moves, self.controller.board),
-                CheckersColor::Black => self.black_player.choose_move(&moves, self.controller.board),
-            };
-            self.controller.execute_move(move_);
-            self.current_player = self.current_player.opposite();
-            return Some(());
+        let started = Instant::now();
+        let action = if !captures.is_empty() {
+            RecordedAction::Capture(self.player_mut(self.current_player).choose_capture(&captures, &controller).clone())
+        } else if !moves.is_empty() {
+            RecordedAction::Move(*self.player_mut(self.current_player).choose_move(&moves, &controller))
+        } else {
+            self.finish(Some(self.current_player.opposite()), EndReason::NoActions);
+            return None
+        };
+        let think_time_ms = started.elapsed().as_millis() as u64;
+        self.controller.play(&(&action).into());
+        let player = self.player(self.current_player);
+        let evaluation = player.last_evaluation();
+        let stats = player.last_search_stats();
+        if let Some(stats) = stats {
+            let info = SearchInfo { player: player.name(), color: self.current_player, evaluation, stats };
+            self.observer.search_info(&info);
         }
-        None
+        self.observer.action_chosen(self.current_player, &action);
+        self.observer.board_updated(&self.controller.board);
+        self.record.plies.push(RecordedPly {
+            color: self.current_player,
+            action,
+            board: self.controller.board,
+            white_queen_idle_moves: self.controller.get_white_queen_idle_moves(),
+            black_queen_idle_moves: self.controller.get_black_queen_idle_moves(),
+            timestamp_ms: unix_millis(),
+            think_time_ms,
+            evaluation,
+            stats,
+        });
+        self.current_player = self.current_player.opposite();
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use super::*;
+    use crate::board::Board;
+    use crate::controller::{Figure, JumpChain, Move};
+    use crate::game::estimators::{BoardEstimator, Position, WeightMatrixEstimator};
+    use crate::game::player::DummyBot;
+    use crate::observer::NullObserver;
+
+    /// Plays the first action and counts how often it was asked.
+    struct FirstActionBot {
+        color: CheckersColor,
+        calls: Arc<AtomicUsize>,
     }
-}
\ No newline at end of file
+
+    impl Player for FirstActionBot {
+        fn choose_move<'a>(&mut self, moves: &'a [Move], _controller: &CheckersController) -> &'a Move {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            &moves[0]
+        }
+
+        fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], _controller: &CheckersController) -> &'a JumpChain {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            &captures[0]
+        }
+
+        fn get_color(&self) -> CheckersColor {
+            self.color
+        }
+
+        fn set_color(&mut self, color: CheckersColor) {
+            self.color = color;
+        }
+
+        fn name(&self) -> String {
+            String::from("FirstActionBot")
+        }
+    }
+
+    #[test]
+    fn player_is_swapped_mid_game() {
+        let mut game = Game::new(CheckersController::new(Board::default()), Box::new(DummyBot::new()), Box::new(DummyBot::new()));
+        game.set_observer(Box::new(NullObserver));
+        game.set_seed(7);
+        game.set_move_limit(60);
+        assert!(game.run_for(10).is_none());
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let bot = FirstActionBot { color: CheckersColor::Black, calls: calls.clone() };
+        let previous = game.set_player(CheckersColor::White, Box::new(bot));
+        assert_eq!(previous.name(), "DummyBot");
+        assert_eq!(game.player(CheckersColor::White).get_color(), CheckersColor::White);
+
+        let outcome = game.run();
+        let record = game.record();
+        assert_eq!(record.outcome, Some(outcome));
+        assert_eq!(record.white, "DummyBot, then FirstActionBot");
+        let white_plies = record.plies.iter().skip(10).filter(|ply| ply.color.is_white()).count();
+        assert!(white_plies > 0);
+        assert_eq!(calls.load(Ordering::Relaxed), white_plies);
+    }
+
+    #[test]
+    fn black_uses_mirrored_or_own_square_weights() {
+        // every square has its own weight, so a wrong mirror shows up in the score
+        let mut weights = [[0.0; 8]; 8];
+        for (x, column) in weights.iter_mut().enumerate() {
+            for (y, weight) in column.iter_mut().enumerate() {
+                *weight = (x * 8 + y) as f64;
+            }
+        }
+        let board = Board::from_positions(&[(2, 2, Figure::Pawn(CheckersColor::White)), (3, 5, Figure::Pawn(CheckersColor::Black))]);
+        let position = Position::from_board(board, CheckersColor::White);
+
+        let mirrored = WeightMatrixEstimator::new(weights, 2.0, 3.0);
+        assert_eq!(mirrored.score(&position), 2.0 * (weights[2][2] - weights[7 - 3][7 - 5]));
+        let terms = mirrored.explain(&position);
+        assert_eq!((terms[0].white, terms[0].black), (weights[2][2], weights[4][2]));
+
+        let mut black_weights = [[0.0; 8]; 8];
+        black_weights[3][5] = 100.0;
+        let own = mirrored.with_black_weights(Some(black_weights));
+        assert_eq!(own.score(&position), 2.0 * (weights[2][2] - 100.0));
+        assert_eq!(own.explain(&position)[0].black, 100.0);
+    }
+}
@@ -0,0 +1,235 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::board::Board;
+use crate::controller::{CheckersColor, CheckersController};
+use crate::game::estimators::{BoardEstimator, Position, ScoreTerm};
+use crate::record::GameRecord;
+use crate::tune::Tunable;
+
+pub const NUM_FEATURES: usize = 6;
+pub const FEATURE_NAMES: [&str; NUM_FEATURES] = ["pawns", "queens", "back rank", "advancement", "center", "edge"];
+
+pub fn side_features(board: &Board) -> ([f64; NUM_FEATURES], [f64; NUM_FEATURES]) {
+    //! White's and black's value of every feature, in the order of `FEATURE_NAMES`.
+    let controller = CheckersController::new(*board);
+    let mut white = [0.0; NUM_FEATURES];
+    let mut black = [0.0; NUM_FEATURES];
+    let positions = controller.get_white_pieces_position().into_iter()
+        .chain(controller.get_black_pieces_position());
+    for (x, y) in positions {
+        let Some(figure) = board.at(x, y) else { continue };
+        let features = if figure.is_white() { &mut white } else { &mut black };
+        // rows counted from the own back rank
+        let row = if figure.is_white() { y } else { 7 - y };
+        if figure.is_queen() {
+            features[1] += 1.0;
+        } else {
+            features[0] += 1.0;
+            features[3] += row as f64 / 7.0;
+            if row == 0 {
+                features[2] += 1.0;
+            }
+        }
+        if (2..=5).contains(&x) && (2..=5).contains(&y) {
+            features[4] += 1.0;
+        }
+        if x == 0 || x == 7 {
+            features[5] += 1.0;
+        }
+    }
+    (white, black)
+}
+
+pub fn features(board: &Board) -> [f64; NUM_FEATURES] {
+    //! White's minus black's value of every feature.
+    let (white, black) = side_features(board);
+    let mut features = white;
+    for (feature, black) in features.iter_mut().zip(black) {
+        *feature -= black;
+    }
+    features
+}
+
+pub fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Weighted sum of `features`, fitted so that `sigmoid(score)` is white's expected result (1 win, 0.5 draw, 0 loss).
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinearEstimator {
+    pub weights: [f64; NUM_FEATURES],
+}
+
+impl Default for LinearEstimator {
+    fn default() -> Self {
+        //! Material only, with the same pawn to queen ratio as `CountEstimator::new(1.0, 3.0)`.
+        let mut weights = [0.0; NUM_FEATURES];
+        weights[0] = 1.0;
+        weights[1] = 3.0;
+        Self { weights }
+    }
+}
+
+impl LinearEstimator {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    fn score_features(&self, features: &[f64; NUM_FEATURES]) -> f64 {
+        self.weights.iter().zip(features).map(|(weight, feature)| weight * feature).sum()
+    }
+}
+
+impl BoardEstimator for LinearEstimator {
+    fn score(&self, position: &Position) -> f64 {
+        self.score_features(&features(&position.board))
+    }
+
+    fn explain(&self, position: &Position) -> Vec<ScoreTerm> {
+        let (white, black) = side_features(&position.board);
+        (0..NUM_FEATURES)
+            .map(|i| ScoreTerm { name: FEATURE_NAMES[i].into(), white: white[i], black: black[i], weight: self.weights[i] })
+            .collect()
+    }
+}
+
+impl Tunable for LinearEstimator {
+    fn parameters(&self) -> Vec<f64> {
+        self.weights.to_vec()
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        let mut weights = self.weights;
+        weights.copy_from_slice(parameters);
+        Self { weights }
+    }
+}
+
+/// Features of the positions of one game in order, labeled with the game's result for white.
+pub struct TrainingGame {
+    positions: Vec<[f64; NUM_FEATURES]>,
+    result: f64,
+}
+
+impl TrainingGame {
+    pub fn from_record(record: &GameRecord) -> Option<Self> {
+        //! `None` for unfinished games.
+        let result = match record.outcome?.winner {
+            Some(CheckersColor::White) => 1.0,
+            Some(CheckersColor::Black) => 0.0,
+            None => 0.5,
+        };
+        let positions = (0..=record.len()).map(|ply| features(&record.controller_at(ply).board)).collect();
+        Some(Self { positions, result })
+    }
+}
+
+/// Fits a `LinearEstimator` to games with TD(λ): every position is trained towards a mix of the model's own
+/// predictions for the following positions and the final result, λ = 1 uses only the results.
+pub struct Trainer {
+    learning_rate: f64,
+    lambda: f64,
+    epochs: usize,
+}
+
+impl Default for Trainer {
+    fn default() -> Self {
+        Self { learning_rate: 0.1, lambda: 0.7, epochs: 100 }
+    }
+}
+
+impl Trainer {
+    pub fn with_learning_rate(mut self, learning_rate: f64) -> Self {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    pub fn with_lambda(mut self, lambda: f64) -> Self {
+        self.lambda = lambda;
+        self
+    }
+
+    pub fn with_epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    fn targets(&self, model: &LinearEstimator, game: &TrainingGame) -> Vec<f64> {
+        //! λ-returns computed backwards from the final result.
+        let mut targets = vec![game.result; game.positions.len()];
+        for i in (0..game.positions.len().saturating_sub(1)).rev() {
+            let next = sigmoid(model.score_features(&game.positions[i + 1]));
+            targets[i] = (1.0 - self.lambda) * next + self.lambda * targets[i + 1];
+        }
+        targets
+    }
+
+    pub fn train(&self, mut model: LinearEstimator, games: &[TrainingGame]) -> (LinearEstimator, f64) {
+        //! Full batch gradient descent on the cross-entropy, returns the fitted model and its final loss.
+        let num_positions: usize = games.iter().map(|game| game.positions.len()).sum();
+        if num_positions == 0 {
+            return (model, 0.0);
+        }
+        let mut loss = 0.0;
+        for _ in 0..self.epochs {
+            let mut gradient = [0.0; NUM_FEATURES];
+            loss = 0.0;
+            for game in games {
+                for (features, target) in game.positions.iter().zip(self.targets(&model, game)) {
+                    let prediction = sigmoid(model.score_features(features)).clamp(1e-9, 1.0 - 1e-9);
+                    loss -= target * prediction.ln() + (1.0 - target) * (1.0 - prediction).ln();
+                    for (g, feature) in gradient.iter_mut().zip(features) {
+                        *g += (prediction - target) * feature;
+                    }
+                }
+            }
+            for (weight, g) in model.weights.iter_mut().zip(gradient) {
+                *weight -= self.learning_rate * g / num_positions as f64;
+            }
+            loss /= num_positions as f64;
+        }
+        (model, loss)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(pawns: &[f64], result: f64) -> TrainingGame {
+        let positions = pawns.iter().map(|&pawns| [pawns, 0.0, 0.0, 0.0, 0.0, 0.0]).collect();
+        TrainingGame { positions, result }
+    }
+
+    #[test]
+    fn targets_mix_predictions_and_result() {
+        let zero = LinearEstimator { weights: [0.0; NUM_FEATURES] };
+        let game = game(&[0.0, 1.0, 1.0], 1.0);
+        assert_eq!(Trainer::default().with_lambda(1.0).targets(&zero, &game), [1.0, 1.0, 1.0]);
+        assert_eq!(Trainer::default().with_lambda(0.0).targets(&zero, &game), [0.5, 0.5, 1.0]);
+        assert_eq!(Trainer::default().with_lambda(0.5).targets(&zero, &game), [0.625, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn training_moves_weights_towards_the_results() {
+        // white wins the games it is a pawn up in and loses those it is a pawn down in
+        let games = [game(&[0.0, 1.0, 1.0, 2.0], 1.0), game(&[0.0, -1.0, -1.0, -2.0], 0.0)];
+        let zero = LinearEstimator { weights: [0.0; NUM_FEATURES] };
+        let trainer = Trainer::default().with_learning_rate(0.5);
+        let (once, first_loss) = trainer.with_epochs(1).train(zero, &games);
+        assert!(once.weights[0] > 0.0);
+        assert!(once.weights[1..].iter().all(|&weight| weight == 0.0), "features that are always 0 keep their weight");
+        let (trained, loss) = Trainer::default().with_learning_rate(0.5).with_epochs(50).train(zero, &games);
+        assert!(trained.weights[0] > once.weights[0] && loss < first_loss);
+        assert!(sigmoid(trained.score_features(&games[0].positions[3])) > 0.8);
+    }
+}
@@ -1,15 +1,57 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use crate::bench::{bench_positions, run_bench, BenchReport};
 use crate::board::{Board, alias, coords_from_alias};
 use crate::controller::{CheckersColor, CheckersController, Figure, Jump, Move};
+use crate::config::{ConfigError, EstimatorConfig};
 use crate::game::{Game};
-use crate::game::estimators::{BoardEstimator, CountEstimator, WeightMatrixEstimator};
-use crate::game::player::{AlphaBetaBot, DummyBot, HumanPlayer, MinMaxBot};
+use crate::game::estimators::{BoardEstimator, CountEstimator, Explanation, Position};
+use crate::game::player::{AlphaBetaBot, DummyBot, HumanPlayer, MinMaxBot, Player};
+use crate::learn::{LinearEstimator, Trainer, TrainingGame, FEATURE_NAMES};
+use crate::mcts::{MctsBot, MctsBudget, PlayoutPolicy};
+use std::sync::Arc;
+use std::time::Duration;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::book::{BookBuilder, OpeningBook};
+use crate::pdn::{parse_fen, parse_pdn, write_pdn};
+use crate::observer::NullObserver;
+use crate::record::GameRecord;
+use crate::replay::Replay;
+use crate::scheduler::{derive_seed, MatchScheduler};
+use crate::search::{RootSearch, SearchEngine};
+use crate::skill::SkillLevel;
+use crate::tablebase::Tablebase;
+use crate::texel::{labeled_positions, parse_positions, write_positions, LabeledPosition, TexelTuner};
+use crate::tournament::{random_opening, Entrant, Tournament, TournamentFormat};
+use crate::tt::TranspositionTable;
+use crate::tune::{GenerationSummary, GeneticTuner};
 
+mod bench;
 mod board;
+mod book;
 mod controller;
 mod colors;
+mod composite;
+mod config;
 mod game;
+mod learn;
+mod mcts;
+mod observer;
+mod pdn;
+mod ponder;
+mod record;
+mod replay;
+mod scheduler;
+mod search;
+mod skill;
+mod stats;
+mod tablebase;
+mod texel;
+mod tournament;
+mod tt;
+mod tune;
 
 macro_rules! mov {
     ($from: ident -> $to: ident) => {{
@@ -39,30 +81,492 @@ fn main() {
     let board = Board::default();
     let controller = CheckersController::new(board);
 
-    let count_estimator = CountEstimator::new(1.0, 3.0);
-    let matrix_estimator = WeightMatrixEstimator::new(
-        [
-            [3.0, 3.0, 3.0, 3.0, 3.0, 3.0, 3.0, 3.0],
-            [3.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 3.0],
-            [3.0, 2.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0],
-            [3.0, 2.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0],
-            [3.0, 2.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0],
-            [3.0, 2.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0],
-            [3.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 3.0],
-            [3.0, 3.0, 3.0, 3.0, 3.0, 3.0, 3.0, 3.0]
-        ], 1.0, 3.0
-    );
+    let mut args: Vec<String> = std::env::args().collect();
+    let book = take_option(&mut args, "--book")
+        .map(|path| Arc::new(OpeningBook::load(&path).unwrap_or_else(|e| panic!("failed to load opening book {path:?}: {e}"))));
+    let tablebase = take_option(&mut args, "--tablebase")
+        .map(|path| Arc::new(Tablebase::load(&path).unwrap_or_else(|e| panic!("failed to load tablebase {path:?}: {e}"))));
+    let model = take_option(&mut args, "--model")
+        .map(|path| LinearEstimator::load(&path).unwrap_or_else(|e| panic!("failed to load model {path:?}: {e}")))
+        .unwrap_or_default();
+    let estimator = take_option(&mut args, "--estimator").unwrap_or_else(|| String::from("count"));
+    let estimator = require_estimator(&estimator, model);
+    let ponder = take_flag(&mut args, "--ponder");
+    let players = PlayerFactory { estimator, model, book, tablebase, ponder };
+    match args.get(1).map(String::as_str) {
+        Some("replay") => replay_command(args, &players),
+        Some("tournament") => tournament_command(args, &players),
+        Some("book") => book_command(args, &players),
+        Some("tablebase") => tablebase_command(&args),
+        Some("eval") => eval_command(&args, &players),
+        Some("bench") => bench_command(args, &players),
+        Some("train") => train_command(args, &players),
+        Some("tune") => tune_command(args, &players),
+        Some("texel") => texel_command(args, &players),
+        _ => play_command(args, &players, controller),
+    }
+}
+
+fn play_command(mut args: Vec<String>, players: &PlayerFactory, controller: CheckersController) {
+    let seed = take_option(&mut args, "--seed").map(|s| s.parse::<u64>().expect("seed should be a number"));
+    let hand_over = take_option(&mut args, "--hand-over").map(|spec| parse_hand_over(&spec, players));
+    let white_spec = args.get(1).map(String::as_str).unwrap_or("alphabeta:10");
+    let black_spec = args.get(2).map(String::as_str).unwrap_or("minmax:6");
+    let white = players.create(white_spec)
+        .unwrap_or_else(|| panic!("unknown player {white_spec:?}"));
+    let black = players.create(black_spec)
+        .unwrap_or_else(|| panic!("unknown player {black_spec:?}"));
+
+    let mut game = Game::new(controller, white, black);
+    if let Some(seed) = seed {
+        game.set_seed(seed);
+    }
+    println!("Game seed: {}", game.record().seed.unwrap());
+    match hand_over {
+        Some((color, plies, player)) => {
+            if game.run_for(plies).is_none() {
+                let previous = game.set_player(color, player);
+                println!("{} hands {color:?} over to {}", previous.name(), game.player(color).name());
+                game.run();
+            }
+        }
+        None => {
+            game.run();
+        }
+    }
+    if let Some(path) = args.get(3) {
+        if path.ends_with(".pdn") {
+            std::fs::write(path, write_pdn(game.record())).expect("failed to save game record");
+        } else {
+            game.record().save(path).expect("failed to save game record");
+        }
+        println!("Game record saved to {path}");
+    }
+}
+
+fn parse_hand_over(spec: &str, players: &PlayerFactory) -> (CheckersColor, usize, Box<dyn Player>) {
+    //! `<white|black>:<plies>:<player>`, the player of that color is replaced after the given number of plies.
+    let usage = "usage: --hand-over <white|black>:<plies>:<player>";
+    let mut parts = spec.splitn(3, ':');
+    let color = match parts.next() {
+        Some("white") => CheckersColor::White,
+        Some("black") => CheckersColor::Black,
+        _ => panic!("{usage}"),
+    };
+    let plies = parts.next().and_then(|p| p.parse::<usize>().ok()).expect(usage);
+    let player_spec = parts.next().expect(usage);
+    let player = players.create(player_spec).unwrap_or_else(|| panic!("unknown player {player_spec:?}"));
+    (color, plies, player)
+}
+
+fn replay_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers replay <record.json> [analysis depth] [blunder threshold] [--explain <estimator>]";
+    let explain = take_option(&mut args, "--explain").unwrap_or_else(|| String::from("positional"));
+    let path = args.get(2).expect(usage);
+    let record = GameRecord::load(path).expect("failed to load game record");
+    let mut replay = Replay::new(record)
+        .with_estimator(Box::new(require_estimator(&explain, players.model)));
+    if let Some(threshold) = args.get(4) {
+        replay = replay.with_blunder_threshold(threshold.parse().expect("blunder threshold should be a number"));
+    }
+    if let Some(depth) = args.get(3) {
+        let depth = depth.parse::<usize>().expect("analysis depth should be a number");
+        replay.analyse(&AlphaBetaBot::new(players.estimator.clone(), depth));
+    }
+    replay.run_interactive();
+}
+
+fn scheduler_options(args: &mut Vec<String>, usage: &str) -> MatchScheduler {
+    //! Reads `--parallel`, `--threads` and `--seed`, the seed is printed so the run can be repeated.
+    let parallel = take_option(args, "--parallel").map(|p| p.parse::<usize>().expect(usage)).unwrap_or(1);
+    let threads = take_option(args, "--threads").map(|t| t.parse::<usize>().expect(usage))
+        .unwrap_or_else(|| (rayon::current_num_threads() / parallel).max(1));
+    let seed = take_option(args, "--seed").map(|s| s.parse::<u64>().expect(usage)).unwrap_or_else(rand::random);
+    println!("Seed: {seed}");
+    MatchScheduler::new(parallel, threads, seed)
+}
+
+fn tournament_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers tournament <round-robin|gauntlet> <games per pairing> <report.txt> <player> <player>... \
+        [--opening-plies <plies>] [--move-limit <plies>] [--parallel <games>] [--threads <per game>] [--seed <seed>]";
+    let scheduler = scheduler_options(&mut args, usage);
+    let opening_plies = take_option(&mut args, "--opening-plies").map(|p| p.parse::<usize>().expect(usage));
+    let move_limit = take_option(&mut args, "--move-limit").map(|p| p.parse::<usize>().expect(usage));
+    let format = match args.get(2).map(String::as_str) {
+        Some("round-robin") => TournamentFormat::RoundRobin,
+        Some("gauntlet") => TournamentFormat::Gauntlet,
+        _ => panic!("{usage}"),
+    };
+    let games = args.get(3).and_then(|g| g.parse::<usize>().ok()).expect(usage);
+    let report_path = args.get(4).expect(usage);
+    let entrants: Vec<Entrant> = args.iter().skip(5)
+        .map(|spec| {
+            assert!(players.create(spec).is_some(), "unknown player {spec:?}");
+            let owned = spec.clone();
+            let players = players.clone();
+            Entrant::new(spec, move || players.create(&owned).unwrap())
+        })
+        .collect();
+    assert!(entrants.len() >= 2, "{usage}");
+    let mut tournament = Tournament::new(entrants, format)
+        .with_games_per_pairing(games)
+        .with_scheduler(scheduler);
+    if let Some(plies) = opening_plies {
+        tournament = tournament.with_opening_plies(plies);
+    }
+    if let Some(plies) = move_limit {
+        tournament = tournament.with_move_limit(plies);
+    }
+    let report = tournament.run();
+    println!("{report}");
+    report.write_to(report_path).expect("failed to write tournament report");
+}
+
+fn book_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers book self-play <games> <depth> <plies> <out.book> [--parallel <games>] [--threads <per game>] [--seed <seed>]\n\
+        \x20      checkers book pdn <plies> <out.book> <games.pdn>...";
+    let mut builder;
+    let out;
+    match args.get(2).map(String::as_str) {
+        Some("self-play") => {
+            let scheduler = scheduler_options(&mut args, usage);
+            let games = args.get(3).and_then(|g| g.parse::<usize>().ok()).expect(usage);
+            let depth = args.get(4).and_then(|d| d.parse::<usize>().ok()).expect(usage);
+            let plies = args.get(5).and_then(|p| p.parse::<usize>().ok()).expect(usage);
+            out = args.get(6).expect(usage).clone();
+            builder = BookBuilder::new(plies, 1);
+            let records = scheduler.run(vec![(); games], |_, _, seed| {
+                let mut game = Game::new(
+                    CheckersController::new(Board::default()),
+                    Box::new(AlphaBetaBot::new(players.estimator.clone(), depth)),
+                    Box::new(AlphaBetaBot::new(players.estimator.clone(), depth)),
+                );
+                game.set_observer(Box::new(NullObserver));
+                game.set_seed(seed);
+                game.set_move_limit(300);
+                game.run();
+                game.into_record()
+            });
+            for record in records {
+                let actions: Vec<_> = record.plies.iter().map(|ply| ply.action.clone()).collect();
+                let winner = record.outcome.and_then(|outcome| outcome.winner);
+                builder.add_game(&record.initial_controller(), CheckersColor::White, &actions, winner);
+            }
+        }
+        Some("pdn") => {
+            let plies = args.get(3).and_then(|p| p.parse::<usize>().ok()).expect(usage);
+            out = args.get(4).expect(usage).clone();
+            builder = BookBuilder::new(plies, 1);
+            for path in args.iter().skip(5) {
+                let text = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("failed to read {path:?}: {e}"));
+                let games = parse_pdn(&text).unwrap_or_else(|e| panic!("failed to parse {path:?}: {e}"));
+                for game in games {
+                    // games with an unknown result carry no information about the quality of the moves
+                    if let Some(winner) = game.result {
+                        builder.add_game(&game.initial, game.first_to_move, &game.actions, winner);
+                    }
+                }
+            }
+        }
+        _ => panic!("{usage}"),
+    }
+    let book = builder.build();
+    book.save(&out).expect("failed to save opening book");
+    println!("Opening book with {} positions saved to {out}", book.len());
+}
 
-    let human = HumanPlayer::new();
-    let dummy = DummyBot::new();
-    let minmax1 = MinMaxBot::new(count_estimator, 6);
-    let minmax2 = MinMaxBot::new(count_estimator, 8);
-    let alpha_beta1 = AlphaBetaBot::new(count_estimator, 10);
-    let mut game = Game::new(
-        controller,
-        alpha_beta1,
-        minmax1
+fn named_estimator(name: &str, model: LinearEstimator) -> Option<Result<EstimatorConfig, ConfigError>> {
+    //! One of `EstimatorConfig::BUILTIN`, `linear` for the `--model` or the path of a config file, `None` if
+    //! `name` is none of them.
+    if name == "linear" {
+        return Some(Ok(EstimatorConfig::Linear(model)));
+    }
+    if let Some(config) = EstimatorConfig::builtin(name) {
+        return Some(Ok(config));
+    }
+    Path::new(name).is_file().then(|| EstimatorConfig::load(name))
+}
+
+fn require_estimator(name: &str, model: LinearEstimator) -> EstimatorConfig {
+    named_estimator(name, model)
+        .unwrap_or_else(|| panic!("unknown estimator {name:?}, expected one of {:?}, linear or a config file", EstimatorConfig::BUILTIN))
+        .unwrap_or_else(|e| panic!("invalid estimator config {name:?}: {e}"))
+}
+
+fn eval_command(args: &[String], players: &PlayerFactory) {
+    let usage = "usage: checkers eval [estimator] [FEN]";
+    let mut args = args.iter().skip(2).peekable();
+    let name = args.next_if(|arg| named_estimator(arg, players.model).is_some())
+        .map(String::as_str)
+        .unwrap_or("positional");
+    let estimator = require_estimator(name, players.model);
+    let (board, side_to_move) = match args.next() {
+        Some(text) => parse_fen(text).unwrap_or_else(|e| panic!("{usage}: {e}")),
+        None => (Board::default(), CheckersColor::White),
+    };
+    println!("{board}");
+    println!("{side_to_move:?} to move, {name} estimator ({})", estimator.kind());
+    println!("{}", Explanation(estimator.explain(&Position::from_board(board, side_to_move))));
+}
+
+fn tablebase_command(args: &[String]) {
+    let usage = "usage: checkers tablebase <max pieces> <out.tb>";
+    let pieces = args.get(2).and_then(|p| p.parse::<u32>().ok()).expect(usage);
+    let out = args.get(3).expect(usage);
+    if pieces > 3 {
+        println!("Warning: tables for more than 3 pieces take a lot of time and memory to generate");
+    }
+    let started = std::time::Instant::now();
+    let tablebase = Tablebase::generate(pieces);
+    tablebase.save(out).expect("failed to save tablebase");
+    println!(
+        "Tablebase with {} positions up to {} pieces saved to {out} in {:.1}s",
+        tablebase.len(), tablebase.max_pieces(), started.elapsed().as_secs_f64()
     );
-    let winner = game.run();
-    println!("Winner: {winner:?}");
+}
+
+fn bench_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers bench [depth] [positions] [--threads <threads>] [--seed <seed>]";
+    let seed = take_option(&mut args, "--seed").map(|s| s.parse::<u64>().expect(usage)).unwrap_or(0);
+    let threads = take_option(&mut args, "--threads").map(|t| t.parse::<usize>().expect(usage))
+        .unwrap_or_else(rayon::current_num_threads);
+    let depth = args.get(2).map(|d| d.parse::<usize>().expect(usage)).unwrap_or(12);
+    let count = args.get(3).map(|c| c.parse::<usize>().expect(usage)).unwrap_or(4);
+    let positions = bench_positions(count, 6, seed);
+    let window = Some(SearchEngine::<CountEstimator>::ASPIRATION_WINDOW);
+    let configurations = [
+        ("alpha-beta", false, None, false, RootSearch::Independent),
+        ("pvs", true, None, false, RootSearch::Independent),
+        ("pvs + aspiration", true, window, false, RootSearch::Independent),
+        ("pvs + tt", true, None, true, RootSearch::Independent),
+        ("pvs + tt + ybw", true, None, true, RootSearch::YoungBrothersWait),
+        ("all", true, window, true, RootSearch::YoungBrothersWait),
+    ];
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("failed to build thread pool");
+    let results = configurations
+        .into_iter()
+        .map(|(name, pvs, window, tt, root_search)| {
+            let table = tt.then(|| Arc::new(TranspositionTable::new(TranspositionTable::DEFAULT_ENTRIES)));
+            let mut bot = AlphaBetaBot::new(players.estimator.clone(), depth)
+                .with_pvs(pvs)
+                .with_aspiration_window(window)
+                .with_transposition_table(table)
+                .with_root_search(root_search);
+            pool.install(|| run_bench(name, &mut bot, &positions))
+        })
+        .collect();
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    println!("depth {depth}, {count} positions, {threads} threads on {cores} cores");
+    println!("{}", BenchReport { results });
+}
+
+fn train_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers train <games per round> <depth> <out.model> [--rounds <rounds>] [--epochs <epochs>] \
+        [--learning-rate <rate>] [--lambda <lambda>] [--parallel <games>] [--threads <per game>] [--seed <seed>]";
+    let rounds = take_option(&mut args, "--rounds").map(|r| r.parse::<usize>().expect(usage)).unwrap_or(5);
+    let mut trainer = Trainer::default();
+    if let Some(epochs) = take_option(&mut args, "--epochs") {
+        trainer = trainer.with_epochs(epochs.parse().expect(usage));
+    }
+    if let Some(rate) = take_option(&mut args, "--learning-rate") {
+        trainer = trainer.with_learning_rate(rate.parse().expect(usage));
+    }
+    if let Some(lambda) = take_option(&mut args, "--lambda") {
+        trainer = trainer.with_lambda(lambda.parse().expect(usage));
+    }
+    let scheduler = scheduler_options(&mut args, usage);
+    let games = args.get(2).and_then(|g| g.parse::<usize>().ok()).expect(usage);
+    let depth = args.get(3).and_then(|d| d.parse::<usize>().ok()).expect(usage);
+    let out = args.get(4).expect(usage);
+
+    let mut model = players.model;
+    for round in 0..rounds {
+        // every round plays new games with the latest model, from random openings so they differ
+        let round_seed = derive_seed(scheduler.seed(), round as u64);
+        let records = scheduler.run(vec![(); games], |index, _, seed| {
+            let mut rng = StdRng::seed_from_u64(derive_seed(round_seed, index as u64));
+            let mut game = Game::new(
+                random_opening(4, &mut rng),
+                Box::new(AlphaBetaBot::new(model, depth)),
+                Box::new(AlphaBetaBot::new(model, depth)),
+            );
+            game.set_observer(Box::new(NullObserver));
+            game.set_seed(derive_seed(seed, round as u64));
+            game.set_move_limit(300);
+            game.run();
+            game.into_record()
+        });
+        let training_games: Vec<TrainingGame> = records.iter().filter_map(TrainingGame::from_record).collect();
+        let loss;
+        (model, loss) = trainer.train(model, &training_games);
+        let weights: Vec<String> = FEATURE_NAMES.iter().zip(model.weights)
+            .map(|(name, weight)| format!("{name} {weight:.3}"))
+            .collect();
+        println!("round {}: {} games, loss {loss:.4}, {}", round + 1, training_games.len(), weights.join(", "));
+    }
+    model.save(out).expect("failed to save model");
+    println!("Model saved to {out}");
+}
+
+fn tune_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers tune <estimator> <generations> <out.toml|out.json> [--population <size>] [--depth <depth>] \
+        [--games <per pairing>] [--log <file>] [--parallel <games>] [--threads <per game>] [--seed <seed>]";
+    let population = take_option(&mut args, "--population").map(|p| p.parse::<usize>().expect(usage));
+    let depth = take_option(&mut args, "--depth").map(|d| d.parse::<usize>().expect(usage));
+    let games = take_option(&mut args, "--games").map(|g| g.parse::<usize>().expect(usage));
+    let log_path = take_option(&mut args, "--log");
+    let scheduler = scheduler_options(&mut args, usage);
+    let base = require_estimator(args.get(2).expect(usage), players.model);
+    let generations = args.get(3).and_then(|g| g.parse::<usize>().ok()).expect(usage);
+    let out = args.get(4).expect(usage);
+
+    let mut tuner = GeneticTuner::new(generations, scheduler);
+    if let Some(population) = population {
+        tuner = tuner.with_population(population);
+    }
+    if let Some(depth) = depth {
+        tuner = tuner.with_depth(depth);
+    }
+    if let Some(games) = games {
+        tuner = tuner.with_games_per_pairing(games);
+    }
+    let mut log = String::new();
+    let mut log_generation = |summary: &GenerationSummary| {
+        println!("{summary}");
+        log.push_str(&format!("{summary}\n"));
+    };
+    let best = tuner.run(base, &mut log_generation);
+    best.save(out).unwrap_or_else(|e| panic!("failed to save estimator: {e}"));
+    println!("Best weights saved to {out}");
+    if let Some(path) = log_path {
+        std::fs::write(&path, log).expect("failed to write convergence log");
+        println!("Convergence log saved to {path}");
+    }
+}
+
+fn texel_command(mut args: Vec<String>, players: &PlayerFactory) {
+    let usage = "usage: checkers texel extract <out.txt> <games.pdn>...\n\
+        \x20      checkers texel tune <estimator> <positions.txt> <out.toml|out.json> [--step <step>] [--passes <passes>]";
+    let step = take_option(&mut args, "--step").map(|s| s.parse::<f64>().expect(usage));
+    let passes = take_option(&mut args, "--passes").map(|p| p.parse::<usize>().expect(usage));
+    match args.get(2).map(String::as_str) {
+        Some("extract") => {
+            let out = args.get(3).expect(usage);
+            let mut positions = Vec::new();
+            for path in args.iter().skip(4) {
+                let text = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("failed to read {path:?}: {e}"));
+                let games = parse_pdn(&text).unwrap_or_else(|e| panic!("failed to parse {path:?}: {e}"));
+                positions.extend(games.iter().flat_map(labeled_positions));
+            }
+            std::fs::write(out, write_positions(&positions)).expect("failed to write positions");
+            println!("{} positions saved to {out}", positions.len());
+        }
+        Some("tune") => {
+            let base = require_estimator(args.get(3).expect(usage), players.model);
+            let path = args.get(4).expect(usage);
+            let out = args.get(5).expect(usage);
+            let text = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("failed to read {path:?}: {e}"));
+            let positions = parse_positions(&text).unwrap_or_else(|e| panic!("failed to parse {path:?}: {e}"));
+            let total = positions.len();
+            let positions: Vec<LabeledPosition> = positions.into_iter().filter(LabeledPosition::is_quiet).collect();
+            println!("{} of {total} positions are quiet", positions.len());
+            let mut tuner = TexelTuner::default();
+            if let Some(step) = step {
+                tuner = tuner.with_step(step);
+            }
+            if let Some(passes) = passes {
+                tuner = tuner.with_max_passes(passes);
+            }
+            let log = |pass: usize, error: f64| println!("pass {pass}: error {error:.6}");
+            tuner.tune(base, &positions, log).save(out).unwrap_or_else(|e| panic!("failed to save estimator: {e}"));
+            println!("Tuned weights saved to {out}");
+        }
+        _ => panic!("{usage}"),
+    }
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    //! Removes `name` from the arguments and returns whether it was there.
+    let index = args.iter().position(|arg| arg == name);
+    if let Some(index) = index {
+        args.remove(index);
+    }
+    index.is_some()
+}
+
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    //! Removes `name value` from the arguments and returns the value.
+    let index = args.iter().position(|arg| arg == name)?;
+    assert!(index + 1 < args.len(), "missing value for {name}");
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Some(value)
+}
+
+#[derive(Clone)]
+struct PlayerFactory {
+    /// Estimator of the `minmax`, `mcts` and `alphabeta` players, chosen with `--estimator`.
+    estimator: EstimatorConfig,
+    /// Estimator of the `linear` players, loaded with `--model`.
+    model: LinearEstimator,
+    book: Option<Arc<OpeningBook>>,
+    tablebase: Option<Arc<Tablebase>>,
+    /// Whether alpha-beta players search on the opponent's time, set with `--ponder`.
+    ponder: bool,
+}
+
+impl PlayerFactory {
+    fn create(&self, spec: &str) -> Option<Box<dyn Player>> {
+        //! Builds a player from a `kind[:depth]` description, e.g. `human`, `dummy`, `minmax:6` or `alphabeta:10`.
+        //! For `mcts` and `mcts-greedy` the number is the iteration budget, for `mcts-ms` the time budget in ms,
+        //! optionally followed by the rollouts per iteration, e.g. `mcts:2000:8`.
+        //! `alphabeta` takes a skill level as last part, e.g. `alphabeta:beginner` or `alphabeta:8:casual`.
+        //! Any other kind names an estimator, e.g. `positional:8` or `tuned.toml:6`, played by an `AlphaBetaBot`.
+        let mut parts: Vec<&str> = spec.split(':').collect();
+        let kind = parts.remove(0);
+        let skill_level = match parts.last().map(|part| part.parse::<SkillLevel>()) {
+            Some(Ok(level)) if kind == "alphabeta" => {
+                parts.pop();
+                level
+            }
+            _ => SkillLevel::Master,
+        };
+        let depth = parts.first().map(|d| d.parse::<usize>()).transpose().ok()?;
+        let rollouts = parts.get(1).map(|r| r.parse::<usize>()).transpose().ok()?.unwrap_or(MctsBot::<EstimatorConfig>::DEFAULT_ROLLOUTS);
+        match kind {
+            "human" => Some(Box::new(HumanPlayer::new())),
+            "dummy" => Some(Box::new(DummyBot::new())),
+            "minmax" => Some(Box::new(MinMaxBot::new(self.estimator.clone(), depth.unwrap_or(6)))),
+            "mcts" => {
+                let budget = MctsBudget::Iterations(depth.unwrap_or(2000));
+                Some(Box::new(MctsBot::new(self.estimator.clone(), budget).with_rollouts(rollouts)))
+            }
+            "mcts-ms" => {
+                let budget = MctsBudget::Time(Duration::from_millis(depth.unwrap_or(1000) as u64));
+                Some(Box::new(MctsBot::new(self.estimator.clone(), budget).with_rollouts(rollouts)))
+            }
+            "mcts-greedy" => {
+                let budget = MctsBudget::Iterations(depth.unwrap_or(2000));
+                let policy = PlayoutPolicy::Estimator { epsilon: 0.1 };
+                Some(Box::new(MctsBot::new(self.estimator.clone(), budget).with_policy(policy).with_rollouts(rollouts)))
+            }
+            "alphabeta" => {
+                let mut bot = AlphaBetaBot::new(self.estimator.clone(), depth.unwrap_or(10))
+                    .with_skill_level(skill_level)
+                    .with_pondering(self.ponder);
+                if let Some(book) = &self.book {
+                    bot = bot.with_opening_book(book.clone());
+                }
+                if let Some(tablebase) = &self.tablebase {
+                    bot = bot.with_tablebase(tablebase.clone());
+                }
+                Some(Box::new(bot))
+            }
+            other => {
+                let estimator = named_estimator(other, self.model)?
+                    .unwrap_or_else(|e| panic!("invalid estimator config {other:?}: {e}"));
+                Some(Box::new(AlphaBetaBot::new(estimator, depth.unwrap_or(8)).with_pondering(self.ponder)))
+            }
+        }
+    }
 }
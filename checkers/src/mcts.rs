@@ -0,0 +1,419 @@
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rayon::prelude::*;
+use crate::board::Board;
+use crate::controller::{CheckersAction, CheckersColor, CheckersController, JumpChain, Move};
+use crate::game::estimators::{BoardEstimator, Position};
+use crate::game::player::{DummyBot, Player};
+use crate::scheduler::derive_seed;
+use crate::stats::SearchStats;
+
+/// How actions are picked during the random playouts of `MctsBot`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlayoutPolicy {
+    /// Uniformly random actions, played by a `DummyBot`.
+    Random,
+    /// The action with the best estimator score one ply ahead, a random one with probability `epsilon`.
+    Estimator { epsilon: f64 },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MctsBudget {
+    Iterations(usize),
+    Time(Duration),
+}
+
+fn idle_moves(controller: &CheckersController, color: CheckersColor) -> u8 {
+    match color {
+        CheckersColor::White => controller.get_white_queen_idle_moves(),
+        CheckersColor::Black => controller.get_black_queen_idle_moves(),
+    }
+}
+
+fn legal_actions(controller: &CheckersController, side_to_move: CheckersColor) -> Vec<CheckersAction> {
+    //! No actions once the side to move has exceeded the idle queen moves, the game is lost then.
+    if idle_moves(controller, side_to_move) > 8 {
+        return Vec::new();
+    }
+    controller.actions(side_to_move)
+}
+
+struct Node {
+    controller: CheckersController,
+    side_to_move: CheckersColor,
+    actions: Vec<CheckersAction>,
+    /// Child reached by every action, `None` until expanded.
+    children: Vec<Option<usize>>,
+    visits: f64,
+    /// Sum of the playout results for the side that moved into this node (1 win, 0.5 draw, 0 loss).
+    reward: f64,
+}
+
+impl Node {
+    fn new(controller: CheckersController, side_to_move: CheckersColor) -> Self {
+        let actions = legal_actions(&controller, side_to_move);
+        let children = vec![None; actions.len()];
+        Self { controller, side_to_move, actions, children, visits: 0.0, reward: 0.0 }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// Monte Carlo tree search with UCT selection.
+///
+/// Every iteration descends the tree, expands one action and plays `rollouts` games from the new position
+/// in parallel, all of them counting towards the nodes on the path. The number of rollouts is fixed so the
+/// results do not depend on the number of threads. The subtree of the position reached after
+/// the opponent's reply is kept for the next move.
+pub struct MctsBot<T> {
+    estimator: T,
+    color: CheckersColor,
+    policy: PlayoutPolicy,
+    budget: MctsBudget,
+    exploration: f64,
+    rollouts: usize,
+    max_playout_plies: usize,
+    tree: Vec<Node>,
+    rng: StdRng,
+    last_stats: Option<SearchStats>,
+    last_evaluation: Option<f64>,
+}
+
+impl <T> MctsBot<T> {
+    pub const DEFAULT_ROLLOUTS: usize = 4;
+
+    pub fn new(estimator: T, budget: MctsBudget) -> Self {
+        Self {
+            estimator,
+            color: CheckersColor::White,
+            policy: PlayoutPolicy::Random,
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            rollouts: Self::DEFAULT_ROLLOUTS,
+            max_playout_plies: 200,
+            tree: Vec::new(),
+            rng: StdRng::from_entropy(),
+            last_stats: None,
+            last_evaluation: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: PlayoutPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_rollouts(mut self, rollouts: usize) -> Self {
+        //! Games played from every expanded position, at least one.
+        self.rollouts = rollouts.max(1);
+        self
+    }
+
+    fn uct_child(&self, node: &Node) -> usize {
+        //! Index of the action maximizing the UCT value, every action must be expanded.
+        let log_visits = node.visits.ln();
+        let value = |child: usize| {
+            let child = &self.tree[child];
+            child.reward / child.visits + self.exploration * (log_visits / child.visits).sqrt()
+        };
+        (0..node.children.len())
+            .max_by(|&a, &b| value(node.children[a].unwrap()).total_cmp(&value(node.children[b].unwrap())))
+            .expect("a non terminal node has actions")
+    }
+
+    fn reuse_tree(&mut self, controller: &CheckersController) {
+        //! Keeps the subtree of `controller` if it is among the positions two plies below the previous root.
+        let found = self.tree.first().and_then(|root| {
+            root.children.iter().flatten().flat_map(|&child| self.tree[child].children.iter().flatten())
+                .copied()
+                .find(|&grandchild| {
+                    let node = &self.tree[grandchild];
+                    node.controller.board == controller.board
+                        && idle_moves(&node.controller, self.color) == idle_moves(controller, self.color)
+                        && idle_moves(&node.controller, self.color.opposite()) == idle_moves(controller, self.color.opposite())
+                        && node.side_to_move == self.color
+                })
+        });
+        match found {
+            Some(root) => {
+                let mut tree = Vec::new();
+                self.extract(root, &mut tree);
+                self.tree = tree;
+            }
+            None => {
+                self.tree = vec![Node::new(controller.clone(), self.color)];
+            }
+        }
+    }
+
+    fn extract(&mut self, index: usize, tree: &mut Vec<Node>) -> usize {
+        //! Moves the subtree rooted at `index` into `tree`, returns its new index.
+        let new_index = tree.len();
+        let placeholder = Node { controller: CheckersController::new(Board::empty()), side_to_move: CheckersColor::White, actions: Vec::new(), children: Vec::new(), visits: 0.0, reward: 0.0 };
+        let node = std::mem::replace(&mut self.tree[index], placeholder);
+        let children = node.children.clone();
+        tree.push(node);
+        for (i, child) in children.into_iter().enumerate() {
+            tree[new_index].children[i] = child.map(|child| self.extract(child, tree));
+        }
+        new_index
+    }
+}
+
+impl <T: BoardEstimator + Sync> MctsBot<T> {
+    fn playout(&self, controller: &CheckersController, side_to_move: CheckersColor, seed: u64) -> Option<CheckersColor> {
+        //! Plays the game out and returns the winner, `None` for a draw.
+        let mut controller = controller.clone();
+        let mut color = side_to_move;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut dummy = DummyBot::with_seed(seed);
+        for _ in 0..self.max_playout_plies {
+            if idle_moves(&controller, color) > 8 {
+                return Some(color.opposite());
+            }
+            let (captures, moves) = controller.options(color);
+            let action = match self.policy {
+                PlayoutPolicy::Random if !captures.is_empty() => {
+                    CheckersAction::JumpChain(dummy.choose_capture(&captures, &controller).clone())
+                }
+                PlayoutPolicy::Random if !moves.is_empty() => {
+                    CheckersAction::Move(*dummy.choose_move(&moves, &controller))
+                }
+                PlayoutPolicy::Estimator { epsilon } if !captures.is_empty() || !moves.is_empty() => {
+                    self.greedy_action(&controller, color, captures, moves, epsilon, &mut rng)
+                }
+                _ => return Some(color.opposite()),
+            };
+            controller.play(&action);
+            color = color.opposite();
+        }
+        let score = self.estimator.score(&Position::new(&controller, color));
+        if score > 0.0 {
+            Some(CheckersColor::White)
+        } else if score < 0.0 {
+            Some(CheckersColor::Black)
+        } else {
+            None
+        }
+    }
+
+    fn greedy_action(
+        &self,
+        controller: &CheckersController,
+        color: CheckersColor,
+        captures: Vec<JumpChain>,
+        moves: Vec<Move>,
+        epsilon: f64,
+        rng: &mut StdRng,
+    ) -> CheckersAction {
+        let actions: Vec<CheckersAction> = if captures.is_empty() {
+            moves.into_iter().map(CheckersAction::Move).collect()
+        } else {
+            captures.into_iter().map(CheckersAction::JumpChain).collect()
+        };
+        if rng.gen::<f64>() < epsilon {
+            return actions.choose(rng).unwrap().clone();
+        }
+        let sign = if color.is_white() { 1.0 } else { -1.0 };
+        let scores: Vec<f64> = actions
+            .iter()
+            .map(|action| {
+                let mut child = controller.clone();
+                child.play(action);
+                self.estimator.score(&Position::new(&child, color.opposite())) * sign
+            })
+            .collect();
+        let best = scores.iter().cloned().fold(f64::MIN, f64::max);
+        let best_actions: Vec<usize> = (0..actions.len()).filter(|&i| scores[i] == best).collect();
+        actions[*best_actions.choose(rng).unwrap()].clone()
+    }
+
+    fn iterate(&mut self, stats: &mut SearchStats) {
+        // selection
+        let mut path = vec![0];
+        let mut index = 0;
+        while !self.tree[index].is_terminal() && self.tree[index].children.iter().all(Option::is_some) {
+            let action = self.uct_child(&self.tree[index]);
+            index = self.tree[index].children[action].unwrap();
+            path.push(index);
+        }
+        // expansion
+        let node = &self.tree[index];
+        let untried: Vec<usize> = (0..node.children.len()).filter(|&i| node.children[i].is_none()).collect();
+        if let Some(&action) = untried.choose(&mut self.rng) {
+            let node = &self.tree[index];
+            let mut controller = node.controller.clone();
+            controller.play(&node.actions[action]);
+            let child = Node::new(controller, node.side_to_move.opposite());
+            self.tree.push(child);
+            let child = self.tree.len() - 1;
+            self.tree[index].children[action] = Some(child);
+            path.push(child);
+            index = child;
+            stats.interior_nodes += 1;
+        }
+        // simulation
+        let seed: u64 = self.rng.gen();
+        let leaf = &self.tree[index];
+        let winners: Vec<Option<CheckersColor>> = if leaf.is_terminal() {
+            vec![Some(leaf.side_to_move.opposite()); self.rollouts]
+        } else {
+            (0..self.rollouts)
+                .into_par_iter()
+                .map(|i| self.playout(&leaf.controller, leaf.side_to_move, derive_seed(seed, i as u64)))
+                .collect()
+        };
+        stats.leaf_evaluations += winners.len() as u64;
+        // backpropagation
+        for &index in &path {
+            let node = &mut self.tree[index];
+            let mover = node.side_to_move.opposite();
+            node.visits += winners.len() as f64;
+            node.reward += winners
+                .iter()
+                .map(|winner| match winner {
+                    Some(winner) if *winner == mover => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                })
+                .sum::<f64>();
+        }
+    }
+
+    fn choose(&mut self, controller: &CheckersController) -> usize {
+        //! Index of the chosen action in the order of `CheckersController::actions`.
+        self.last_evaluation = None;
+        self.last_stats = None;
+        let started = Instant::now();
+        self.reuse_tree(controller);
+        let mut stats = SearchStats::default();
+        let mut iterations = 0;
+        loop {
+            let done = match self.budget {
+                MctsBudget::Iterations(limit) => iterations >= limit,
+                MctsBudget::Time(limit) => started.elapsed() >= limit,
+            };
+            if done && self.tree[0].children.iter().any(Option::is_some) {
+                break;
+            }
+            self.iterate(&mut stats);
+            iterations += 1;
+        }
+        let root = &self.tree[0];
+        let visits = |i: usize| root.children[i].map(|child| self.tree[child].visits).unwrap_or(0.0);
+        let best = (0..root.children.len())
+            .max_by(|&a, &b| visits(a).total_cmp(&visits(b)))
+            .expect("the root has actions");
+        let child = &self.tree[root.children[best].unwrap()];
+        // expected result for the bot mapped to [-1, 1], from white's point of view
+        let sign = if self.color.is_white() { 1.0 } else { -1.0 };
+        self.last_evaluation = Some((2.0 * child.reward / child.visits - 1.0) * sign);
+        stats.nodes = self.tree.len() as u64;
+        stats.elapsed_us = started.elapsed().as_micros() as u64;
+        self.last_stats = Some(stats);
+        best
+    }
+}
+
+impl <T: BoardEstimator + Sync + Send> Player for MctsBot<T> {
+    fn choose_move<'a>(&mut self, moves: &'a [Move], controller: &CheckersController) -> &'a Move {
+        //! The moves given by `Game` are the legal ones in `CheckersController::options` order, the tree uses the same.
+        if moves.len() == 1 {
+            self.last_evaluation = None;
+            self.last_stats = None;
+            return &moves[0];
+        }
+        &moves[self.choose(controller)]
+    }
+
+    fn choose_capture<'a>(&mut self, captures: &'a [JumpChain], controller: &CheckersController) -> &'a JumpChain {
+        if captures.len() == 1 {
+            self.last_evaluation = None;
+            self.last_stats = None;
+            return &captures[0];
+        }
+        &captures[self.choose(controller)]
+    }
+
+    fn get_color(&self) -> CheckersColor {
+        self.color
+    }
+
+    fn set_color(&mut self, color: CheckersColor) {
+        self.color = color;
+        self.tree.clear();
+    }
+
+    fn name(&self) -> String {
+        match self.budget {
+            MctsBudget::Iterations(iterations) => format!("MctsBot(iterations={iterations})"),
+            MctsBudget::Time(limit) => format!("MctsBot(time={}ms)", limit.as_millis()),
+        }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn last_evaluation(&self) -> Option<f64> {
+        self.last_evaluation
+    }
+
+    fn last_search_stats(&self) -> Option<SearchStats> {
+        self.last_stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::{CheckersAct, Figure};
+    use crate::game::Game;
+    use crate::game::estimators::CountEstimator;
+    use crate::observer::NullObserver;
+
+    fn bot(iterations: usize) -> MctsBot<CountEstimator> {
+        MctsBot::new(CountEstimator::new(1.0, 3.0), MctsBudget::Iterations(iterations))
+    }
+
+    #[test]
+    fn one_move_win_is_found() {
+        // the queen on e3 blocks the pawn on h2 by moving to g1, black has no action left then
+        let mut board = Board::empty();
+        board.set(4, 2, Some(Figure::Queen(CheckersColor::White)));
+        board.set(7, 1, Some(Figure::Pawn(CheckersColor::Black)));
+        let controller = CheckersController::new(board);
+        let (captures, moves) = controller.options(CheckersColor::White);
+        assert!(captures.is_empty() && moves.len() > 2);
+        let mut bot = bot(300);
+        bot.reseed(1);
+        let chosen = bot.choose_move(&moves, &controller);
+        assert_eq!(chosen.end_position(), (6, 0));
+        assert!(bot.last_evaluation().unwrap() > 0.9, "the win is certain");
+    }
+
+    fn seeded_game(seed: u64, threads: usize) -> Vec<Board> {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| {
+            let mut game = Game::new(CheckersController::new(Board::default()), Box::new(bot(100)), Box::new(DummyBot::new()));
+            game.set_observer(Box::new(NullObserver));
+            game.set_seed(seed);
+            game.set_move_limit(12);
+            game.run();
+            game.into_record().plies.iter().map(|ply| ply.board).collect()
+        })
+    }
+
+    #[test]
+    fn same_seed_picks_the_same_moves() {
+        // the bot keeps its tree between its moves, the game replays it with the same seed on any number of threads
+        let game = seeded_game(3, 1);
+        assert_eq!(game.len(), 12);
+        assert_eq!(seeded_game(3, 3), game);
+        assert_ne!(seeded_game(4, 1), game);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::board::Board;
+use crate::controller::CheckersColor;
+use crate::record::{GameOutcome, RecordedAction};
+use crate::search::format_score;
+use crate::stats::SearchStats;
+
+/// What a searching player reported about the action it has just chosen.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub player: String,
+    pub color: CheckersColor,
+    /// Score of the chosen action from white's point of view.
+    pub evaluation: Option<f64>,
+    pub stats: SearchStats,
+}
+
+/// Receives the events of a `Game`. Every method does nothing by default.
+pub trait GameObserver: Send {
+    fn game_started(&mut self, _white: &str, _black: &str, _board: &Board) {}
+    fn board_updated(&mut self, _board: &Board) {}
+    fn action_chosen(&mut self, _color: CheckersColor, _action: &RecordedAction) {}
+    fn search_info(&mut self, _info: &SearchInfo) {}
+    fn game_over(&mut self, _outcome: &GameOutcome) {}
+}
+
+/// Prints the game to the standard output.
+pub struct ConsoleObserver;
+
+impl GameObserver for ConsoleObserver {
+    fn game_started(&mut self, _white: &str, _black: &str, board: &Board) {
+        println!("{board}");
+    }
+
+    fn board_updated(&mut self, board: &Board) {
+        println!("{board}");
+    }
+
+    fn action_chosen(&mut self, _color: CheckersColor, action: &RecordedAction) {
+        println!("{action}");
+    }
+
+    fn search_info(&mut self, info: &SearchInfo) {
+        if let Some(evaluation) = info.evaluation {
+            let sign = if info.color.is_white() { 1.0 } else { -1.0 };
+            println!("{:?} {} best: {}", info.color, info.player, format_score(evaluation * sign));
+        }
+        println!("{}", info.stats);
+    }
+
+    fn game_over(&mut self, outcome: &GameOutcome) {
+        println!("{outcome}");
+    }
+}
+
+/// Ignores every event, for headless runs.
+pub struct NullObserver;
+
+impl GameObserver for NullObserver {}
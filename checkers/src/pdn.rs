@@ -0,0 +1,372 @@
+use std::fmt::{Display, Formatter};
+use crate::board::{alias, coords_from_alias, Board};
+use crate::controller::{CheckersAct, CheckersColor, CheckersController, Figure, JumpChain, Move};
+use crate::record::{GameRecord, RecordedAction};
+
+pub fn square_notation(x: u8, y: u8) -> String {
+    alias(x, y).to_lowercase()
+}
+
+pub fn move_notation(move_: &Move) -> String {
+    let ((x_start, y_start), (x_end, y_end)) = move_.start_end();
+    format!("{}-{}", square_notation(x_start, y_start), square_notation(x_end, y_end))
+}
+
+pub fn capture_notation(capture: &JumpChain) -> String {
+    let (x_start, y_start) = capture.start_position();
+    let mut ret = square_notation(x_start, y_start);
+    for jump in capture.jumps() {
+        let (x_end, y_end) = jump.end_position();
+        ret = format!("{ret}:{}", square_notation(x_end, y_end));
+    }
+    ret
+}
+
+pub fn notation(action: &RecordedAction) -> String {
+    //! Algebraic PDN notation: `c3-d4` for moves, `c3:e5:g7` for captures.
+    match action {
+        RecordedAction::Move(move_) => move_notation(move_),
+        RecordedAction::Capture(capture) => capture_notation(capture),
+    }
+}
+
+pub fn parse_action(controller: &CheckersController, color: CheckersColor, text: &str) -> Option<RecordedAction> {
+    //! Finds the legal action written as `text`. Captures may list every landing square or only the first and last one.
+    let text = text.to_lowercase().replace('x', ":");
+    let actions: Vec<RecordedAction> = controller.actions(color).into_iter().map(RecordedAction::from).collect();
+    if let Some(action) = actions.iter().find(|action| notation(action) == text) {
+        return Some(action.clone());
+    }
+    let squares: Vec<&str> = text.split(':').collect();
+    if squares.len() != 2 {
+        return None;
+    }
+    let mut matching = actions.iter().filter(|action| {
+        let (x_start, y_start) = action.start_position();
+        let (x_end, y_end) = action.end_position();
+        matches!(action, RecordedAction::Capture(_))
+            && square_notation(x_start, y_start) == squares[0]
+            && square_notation(x_end, y_end) == squares[1]
+    });
+    let found = matching.next()?;
+    if matching.next().is_some() {
+        return None;
+    }
+    Some(found.clone())
+}
+
+pub fn fen(board: &Board, side_to_move: CheckersColor) -> String {
+    //! Algebraic FEN, e.g. `W:Wa1,c1,Kd4:Bf8,h8` with `K` marking queens.
+    let mut white = Vec::new();
+    let mut black = Vec::new();
+    for y in 0..8 {
+        for x in 0..8 {
+            if let Some(figure) = board.at(x, y) {
+                let prefix = if figure.is_queen() { "K" } else { "" };
+                let square = format!("{prefix}{}", square_notation(x, y));
+                if figure.is_white() { white.push(square) } else { black.push(square) }
+            }
+        }
+    }
+    let side = if side_to_move.is_white() { "W" } else { "B" };
+    format!("{side}:W{}:B{}", white.join(","), black.join(","))
+}
+
+pub fn parse_fen(text: &str) -> Result<(Board, CheckersColor), PdnError> {
+    let text = text.trim().trim_end_matches('.');
+    let mut parts = text.split(':');
+    let side = match parts.next() {
+        Some("W") => CheckersColor::White,
+        Some("B") => CheckersColor::Black,
+        _ => return Err(PdnError::new(format!("invalid side to move in FEN {text:?}"))),
+    };
+    let mut board = Board::empty();
+    for part in parts {
+        let (color, squares) = match part.chars().next() {
+            Some('W') => (CheckersColor::White, &part[1..]),
+            Some('B') => (CheckersColor::Black, &part[1..]),
+            _ => return Err(PdnError::new(format!("invalid piece list {part:?} in FEN {text:?}"))),
+        };
+        for square in squares.split(',').filter(|square| !square.is_empty()) {
+            let (queen, square) = match square.strip_prefix('K') {
+                Some(square) => (true, square),
+                None => (false, square),
+            };
+            if !is_square(square) {
+                return Err(PdnError::new(format!("invalid square {square:?} in FEN {text:?}")));
+            }
+            let (x, y) = coords_from_alias(square);
+            if x % 2 != y % 2 {
+                return Err(PdnError::new(format!("square {square:?} in FEN {text:?} is not playable")));
+            }
+            let figure = if queen { Figure::Queen(color) } else { Figure::Pawn(color) };
+            board.set(x, y, Some(figure));
+        }
+    }
+    Ok((board, side))
+}
+
+fn is_square(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!((chars.next(), chars.next(), chars.next()), (Some('a'..='h' | 'A'..='H'), Some('1'..='8'), None))
+}
+
+#[derive(Debug)]
+pub struct PdnError {
+    pub message: String,
+}
+
+impl PdnError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for PdnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for PdnError {}
+
+pub struct PdnGame {
+    pub headers: Vec<(String, String)>,
+    pub initial: CheckersController,
+    pub first_to_move: CheckersColor,
+    pub actions: Vec<RecordedAction>,
+    /// `Some(None)` is a draw, `None` an unknown or unfinished result.
+    pub result: Option<Option<CheckersColor>>,
+}
+
+impl PdnGame {
+    fn new() -> Self {
+        Self {
+            headers: Vec::new(),
+            initial: CheckersController::new(Board::default()),
+            first_to_move: CheckersColor::White,
+            actions: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn positions(&self) -> Vec<(CheckersController, CheckersColor)> {
+        //! Every position of the game with the side to move, the final one included.
+        let mut controller = CheckersController::with_idle_moves(
+            self.initial.board,
+            self.initial.get_white_queen_idle_moves(),
+            self.initial.get_black_queen_idle_moves(),
+        );
+        let mut color = self.first_to_move;
+        let mut ret = Vec::with_capacity(self.actions.len() + 1);
+        for action in &self.actions {
+            ret.push((controller.clone(), color));
+            controller.play(&action.into());
+            color = color.opposite();
+        }
+        ret.push((controller, color));
+        ret
+    }
+}
+
+enum Token {
+    Header(String, String),
+    Move(String),
+    Result(Option<Option<CheckersColor>>),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PdnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' => {
+                chars.next();
+                let header: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let header = header.trim();
+                let (key, value) = header.split_once(char::is_whitespace)
+                    .ok_or_else(|| PdnError::new(format!("invalid header [{header}]")))?;
+                tokens.push(Token::Header(key.to_string(), value.trim().trim_matches('"').to_string()));
+            }
+            '{' => {
+                chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+            }
+            ';' => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            '(' => {
+                let mut depth = 0;
+                for c in chars.by_ref() {
+                    if c == '(' { depth += 1; }
+                    if c == ')' { depth -= 1; }
+                    if depth == 0 { break; }
+                }
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[{;(".contains(c) { break; }
+                    word.push(c);
+                    chars.next();
+                }
+                let word = word.trim_end_matches(['!', '?']);
+                match word {
+                    "1-0" | "2-0" => tokens.push(Token::Result(Some(Some(CheckersColor::White)))),
+                    "0-1" | "0-2" => tokens.push(Token::Result(Some(Some(CheckersColor::Black)))),
+                    "1/2-1/2" | "1-1" => tokens.push(Token::Result(Some(None))),
+                    "*" => tokens.push(Token::Result(None)),
+                    _ => {
+                        let without_number = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                        if !without_number.is_empty() {
+                            tokens.push(Token::Move(without_number.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+pub fn parse_pdn(text: &str) -> Result<Vec<PdnGame>, PdnError> {
+    //! Parses every game of a PDN file written in algebraic notation. Comments and variations are skipped.
+    let mut games = Vec::new();
+    let mut game = PdnGame::new();
+    let mut controller = game.initial.clone();
+    let mut color = game.first_to_move;
+    let mut has_moves = false;
+    for token in tokenize(text)? {
+        match token {
+            Token::Header(key, value) => {
+                if has_moves {
+                    games.push(std::mem::replace(&mut game, PdnGame::new()));
+                    has_moves = false;
+                }
+                if key == "FEN" {
+                    let (board, side) = parse_fen(&value)?;
+                    game.initial = CheckersController::new(board);
+                    game.first_to_move = side;
+                }
+                controller = game.initial.clone();
+                color = game.first_to_move;
+                game.headers.push((key, value));
+            }
+            Token::Move(text) => {
+                let action = parse_action(&controller, color, &text).ok_or_else(|| PdnError::new(format!(
+                    "illegal or ambiguous move {text:?} for {color:?} in game {}", games.len() + 1
+                )))?;
+                controller.play(&(&action).into());
+                color = color.opposite();
+                game.actions.push(action);
+                has_moves = true;
+            }
+            Token::Result(result) => {
+                game.result = result;
+                games.push(std::mem::replace(&mut game, PdnGame::new()));
+                controller = game.initial.clone();
+                color = game.first_to_move;
+                has_moves = false;
+            }
+        }
+    }
+    if has_moves || !game.headers.is_empty() {
+        games.push(game);
+    }
+    Ok(games)
+}
+
+pub fn write_pdn(record: &GameRecord) -> String {
+    let result = match record.outcome.map(|outcome| outcome.winner) {
+        Some(Some(CheckersColor::White)) => "1-0",
+        Some(Some(CheckersColor::Black)) => "0-1",
+        Some(None) => "1/2-1/2",
+        None => "*",
+    };
+    let mut ret = format!("[White \"{}\"]\n[Black \"{}\"]\n[Result \"{result}\"]\n", record.white, record.black);
+    if let Some(seed) = record.seed {
+        ret = format!("{ret}[Seed \"{seed}\"]\n");
+    }
+    let first_to_move = record.plies.first().map(|ply| ply.color).unwrap_or(CheckersColor::White);
+    if record.initial_board != Board::default() || !first_to_move.is_white() {
+        ret = format!("{ret}[FEN \"{}\"]\n", fen(&record.initial_board, first_to_move));
+    }
+    ret.push('\n');
+    let mut line = String::new();
+    for (i, ply) in record.plies.iter().enumerate() {
+        let mut word = notation(&ply.action);
+        if i % 2 == 0 {
+            word = format!("{}. {word}", i / 2 + 1);
+        }
+        if line.len() + word.len() + 1 > 80 {
+            ret = format!("{ret}{}\n", line.trim_end());
+            line.clear();
+        }
+        line = format!("{line}{word} ");
+    }
+    format!("{ret}{line}{result}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::game::player::DummyBot;
+    use crate::observer::NullObserver;
+
+    #[test]
+    fn game_round_trip() {
+        let mut game = Game::new(CheckersController::new(Board::default()), Box::new(DummyBot::new()), Box::new(DummyBot::new()));
+        game.set_observer(Box::new(NullObserver));
+        game.set_seed(5);
+        game.set_move_limit(80);
+        game.run();
+        let record = game.record();
+        assert!(record.plies.iter().any(|ply| matches!(ply.action, RecordedAction::Capture(_))), "the game should capture");
+
+        let games = parse_pdn(&write_pdn(record)).expect("written games should parse");
+        assert_eq!(games.len(), 1);
+        let parsed = &games[0];
+        assert!(parsed.headers.contains(&(String::from("Seed"), String::from("5"))));
+        assert_eq!(parsed.result, record.outcome.map(|outcome| outcome.winner));
+        let written: Vec<String> = record.plies.iter().map(|ply| notation(&ply.action)).collect();
+        let read: Vec<String> = parsed.actions.iter().map(notation).collect();
+        assert_eq!(read, written);
+        let boards: Vec<Board> = parsed.positions().iter().skip(1).map(|(controller, _)| controller.board).collect();
+        assert_eq!(boards, record.plies.iter().map(|ply| ply.board).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn captures_may_skip_intermediate_squares() {
+        let games = parse_pdn("[FEN \"W:Wc3:Bd4,f6\"]\n1. c3xg7 1-0").expect("the capture is legal");
+        assert_eq!(games[0].actions.iter().map(notation).collect::<Vec<_>>(), ["c3:e5:g7"]);
+        assert_eq!(games[0].result, Some(Some(CheckersColor::White)));
+        let (last, side) = games[0].positions().pop().unwrap();
+        assert_eq!(fen(&last.board, side), "B:Wg7:B");
+    }
+
+    #[test]
+    fn malformed_moves_are_errors() {
+        for text in ["1. c3-d5 *", "1. c3-d4 c3-d4 *", "1. x9-d4 *", "1. c3-b4 f6-e5 2. b4xf8 *"] {
+            let error = parse_pdn(text).err().unwrap_or_else(|| panic!("{text:?} should not parse"));
+            assert!(error.message.starts_with("illegal or ambiguous move"), "{text:?}: {error}");
+        }
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let text = "B:Wa1,Kd4:BKh2,f8";
+        let (board, side) = parse_fen(text).expect("valid FEN");
+        assert_eq!(side, CheckersColor::Black);
+        assert_eq!(board.at(3, 3), Some(Figure::Queen(CheckersColor::White)));
+        assert_eq!(board.at(7, 1), Some(Figure::Queen(CheckersColor::Black)));
+        assert_eq!(fen(&board, side), text);
+        let start = fen(&Board::default(), CheckersColor::White);
+        assert_eq!(fen(&parse_fen(&start).expect("valid FEN").0, CheckersColor::White), start);
+        for invalid in ["X:Wa1:Bh8", "W:Wb1:Bh8", "W:Wi9:Bh8", "W:Qa1:Bh8"] {
+            assert!(parse_fen(invalid).is_err(), "{invalid:?} should be rejected");
+        }
+    }
+}
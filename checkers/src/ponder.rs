@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use crate::controller::{CheckersColor, CheckersController};
+use crate::game::estimators::BoardEstimator;
+use crate::search::SearchEngine;
+use crate::tt::position_key;
+
+/// Background search of the position expected after the opponent's reply, running while the opponent thinks.
+/// Its scores are thrown away, the work is reused through the transposition table it shares with the bot.
+pub struct Ponder {
+    /// Position being searched, with `color` to move.
+    controller: CheckersController,
+    color: CheckersColor,
+    stop: Arc<AtomicBool>,
+    /// Receives once the search has returned, in a mutex so bots stay `Sync`.
+    done: Option<Mutex<Receiver<()>>>,
+}
+
+impl Ponder {
+    pub fn pool() -> ThreadPool {
+        //! Pool for pondering with as many threads as the pool the bot plays in, so a game limited to a few threads
+        //! stays within them. The game's own pool cannot be used: a thread of the opponent's search waiting for
+        //! work could pick up the ponder search and would not return before the search is stopped.
+        ThreadPoolBuilder::new()
+            .num_threads(rayon::current_num_threads())
+            .build()
+            .expect("failed to build thread pool")
+    }
+
+    pub fn start<T: BoardEstimator + Send + Sync + 'static>(
+        engine: &SearchEngine<T>,
+        pool: &ThreadPool,
+        after_own_action: &CheckersController,
+        color: CheckersColor,
+    ) -> Option<Self> {
+        //! Predicts the opponent's reply from the transposition table and starts searching the resulting position
+        //! for `color` in `pool`. `None` without a prediction or when the bot would not have to search after the
+        //! reply.
+        let reply = engine.predicted_reply(after_own_action, color.opposite())?;
+        let actions = reply.actions(color);
+        if actions.len() < 2 {
+            return None;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let engine = engine.clone().with_stop_flag(Some(stop.clone()));
+        let controller = reply.clone();
+        let (sender, done) = mpsc::channel();
+        pool.spawn(move || {
+            engine.score_actions(&reply, &actions, color);
+            let _ = sender.send(());
+        });
+        Some(Self { controller, color, stop, done: Some(Mutex::new(done)) })
+    }
+
+    pub fn finish(mut self, controller: &CheckersController) -> bool {
+        //! Stops the search and returns whether `controller`, the position actually reached, is the pondered one.
+        self.stop();
+        position_key(&self.controller, self.color) == position_key(controller, self.color)
+    }
+
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(done) = self.done.take() {
+            done.into_inner().unwrap().recv().expect("ponder search panicked");
+        }
+    }
+}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
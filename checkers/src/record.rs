@@ -0,0 +1,169 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::board::Board;
+use crate::controller::{CheckersAct, CheckersAction, CheckersColor, CheckersController, JumpChain, Move};
+use crate::stats::SearchStats;
+
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RecordedAction {
+    Move(Move), Capture(JumpChain)
+}
+
+impl RecordedAction {
+    pub fn start_position(&self) -> (u8, u8) {
+        match self {
+            RecordedAction::Move(move_) => move_.start_position(),
+            RecordedAction::Capture(jump_chain) => jump_chain.start_position(),
+        }
+    }
+
+    pub fn end_position(&self) -> (u8, u8) {
+        match self {
+            RecordedAction::Move(move_) => move_.end_position(),
+            RecordedAction::Capture(jump_chain) => jump_chain.end_position(),
+        }
+    }
+
+}
+
+impl From<CheckersAction> for RecordedAction {
+    fn from(action: CheckersAction) -> Self {
+        match action {
+            CheckersAction::Move(move_) => RecordedAction::Move(move_),
+            CheckersAction::Jump(jump) => RecordedAction::Capture(JumpChain::from(jump)),
+            CheckersAction::JumpChain(jump_chain) => RecordedAction::Capture(jump_chain),
+        }
+    }
+}
+
+impl From<&RecordedAction> for CheckersAction {
+    fn from(action: &RecordedAction) -> Self {
+        //! The action to `play` on a `CheckersController`.
+        match action {
+            RecordedAction::Move(move_) => CheckersAction::Move(*move_),
+            RecordedAction::Capture(jump_chain) => CheckersAction::JumpChain(jump_chain.clone()),
+        }
+    }
+}
+
+impl Display for RecordedAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordedAction::Move(move_) => write!(f, "{move_}"),
+            RecordedAction::Capture(jump_chain) => write!(f, "{jump_chain}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedPly {
+    pub color: CheckersColor,
+    pub action: RecordedAction,
+    /// Position after the action was executed and pawns were promoted.
+    pub board: Board,
+    pub white_queen_idle_moves: u8,
+    pub black_queen_idle_moves: u8,
+    pub timestamp_ms: u64,
+    pub think_time_ms: u64,
+    /// Score reported by the player for the chosen action, from white's point of view.
+    pub evaluation: Option<f64>,
+    #[serde(default)]
+    pub stats: Option<SearchStats>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EndReason {
+    NoPieces, NoActions, IdleMoves, MoveLimit
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameOutcome {
+    /// `None` means a draw.
+    pub winner: Option<CheckersColor>,
+    pub reason: EndReason,
+}
+
+impl Display for GameOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.winner {
+            Some(winner) => write!(f, "Winner: {winner:?} ({:?})", self.reason),
+            None => write!(f, "Draw ({:?})", self.reason),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub white: String,
+    pub black: String,
+    pub initial_board: Board,
+    pub white_queen_idle_moves: u8,
+    pub black_queen_idle_moves: u8,
+    pub started_at_ms: u64,
+    /// Seed the players' random number generators were derived from.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub plies: Vec<RecordedPly>,
+    pub outcome: Option<GameOutcome>,
+}
+
+impl GameRecord {
+    pub fn new(white: String, black: String, controller: &CheckersController) -> Self {
+        Self {
+            white,
+            black,
+            initial_board: controller.board,
+            white_queen_idle_moves: controller.get_white_queen_idle_moves(),
+            black_queen_idle_moves: controller.get_black_queen_idle_moves(),
+            started_at_ms: unix_millis(),
+            seed: None,
+            plies: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.plies.len()
+    }
+
+    pub fn initial_controller(&self) -> CheckersController {
+        CheckersController::with_idle_moves(self.initial_board, self.white_queen_idle_moves, self.black_queen_idle_moves)
+    }
+
+    pub fn controller_at(&self, ply: usize) -> CheckersController {
+        //! Position after `ply` actions, `controller_at(0)` being the starting position.
+        if ply == 0 {
+            return self.initial_controller();
+        }
+        let recorded = &self.plies[ply - 1];
+        CheckersController::with_idle_moves(recorded.board, recorded.white_queen_idle_moves, recorded.black_queen_idle_moves)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Ok(Self::from_json(&json)?)
+    }
+}
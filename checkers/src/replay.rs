@@ -0,0 +1,165 @@
+use std::io;
+use rayon::prelude::*;
+use crate::controller::CheckersColor;
+use crate::game::estimators::{BoardEstimator, Explanation, Position};
+use crate::game::player::AlphaBetaBot;
+use crate::record::GameRecord;
+use crate::search::{format_score, win_distance};
+
+pub struct Replay {
+    record: GameRecord,
+    cursor: usize,
+    analysis: Option<Vec<f64>>,
+    blunder_threshold: f64,
+    /// Estimator whose breakdown of the current position `e` shows.
+    estimator: Option<Box<dyn BoardEstimator + Send + Sync>>,
+}
+
+impl Replay {
+    pub fn new(record: GameRecord) -> Self {
+        Self { record, cursor: 0, analysis: None, blunder_threshold: 1.0, estimator: None }
+    }
+
+    pub fn with_estimator(mut self, estimator: Box<dyn BoardEstimator + Send + Sync>) -> Self {
+        self.estimator = Some(estimator);
+        self
+    }
+
+    pub fn explain(&self) -> Option<Explanation> {
+        //! Breakdown of the current position's score by the estimator's terms.
+        let position = Position::new(&self.record.controller_at(self.cursor), self.color_to_move(self.cursor));
+        Some(Explanation(self.estimator.as_ref()?.explain(&position)))
+    }
+
+    pub fn with_blunder_threshold(mut self, threshold: f64) -> Self {
+        self.blunder_threshold = threshold;
+        self
+    }
+
+    pub fn color_to_move(&self, ply: usize) -> CheckersColor {
+        match self.record.plies.get(ply) {
+            Some(recorded) => recorded.color,
+            None => self.record.plies.last().map(|last| last.color.opposite()).unwrap_or(CheckersColor::White),
+        }
+    }
+
+    pub fn analyse<T: BoardEstimator + Sync + Send + 'static>(&mut self, bot: &AlphaBetaBot<T>) {
+        //! Evaluates every position of the game, the same search depth is used for all of them.
+        let evals = (0..=self.record.len())
+            .into_par_iter()
+            .map(|ply| bot.evaluate(&self.record.controller_at(ply), self.color_to_move(ply)))
+            .collect();
+        self.analysis = Some(evals);
+    }
+
+    pub fn evaluation_loss(&self, ply: usize) -> Option<f64> {
+        //! How much the action leading to position `ply` worsened the position for the side that played it.
+        //! Taking longer for a win that stays forced, or resisting a forced loss, loses nothing.
+        let evals = self.analysis.as_ref()?;
+        if ply == 0 {
+            return None;
+        }
+        let mover = self.record.plies[ply - 1].color;
+        let sign = if mover.is_white() { 1.0 } else { -1.0 };
+        let (before, after) = (evals[ply - 1] * sign, evals[ply] * sign);
+        if win_distance(before).is_some() && win_distance(after).is_some() && before.signum() == after.signum() {
+            return Some(0.0);
+        }
+        Some(before - after)
+    }
+
+    pub fn is_blunder(&self, ply: usize) -> bool {
+        self.evaluation_loss(ply).is_some_and(|loss| loss >= self.blunder_threshold)
+    }
+
+    pub fn forward(&mut self) -> bool {
+        if self.cursor < self.record.len() {
+            self.cursor += 1;
+            return true;
+        }
+        false
+    }
+
+    pub fn backward(&mut self) -> bool {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            return true;
+        }
+        false
+    }
+
+    pub fn go_to(&mut self, ply: usize) {
+        self.cursor = ply.min(self.record.len());
+    }
+
+    pub fn render(&self) -> String {
+        let ply = self.cursor;
+        let controller = self.record.controller_at(ply);
+        let mut ret = format!("{} (white) vs {} (black) - ply {ply}/{}\n", self.record.white, self.record.black, self.record.len());
+        if ply == 0 {
+            ret = format!("{ret}Starting position\n");
+        } else {
+            let recorded = &self.record.plies[ply - 1];
+            ret = format!("{ret}{}. {:?}: {}", ply.div_ceil(2), recorded.color, recorded.action);
+            if let Some(eval) = recorded.evaluation {
+                ret = format!("{ret} (player eval {}, {} ms)", format_score(eval), recorded.think_time_ms);
+            }
+            ret = format!("{ret}\n");
+        }
+        ret = format!("{ret}{}", controller.board);
+        if let Some(evals) = &self.analysis {
+            ret = format!("{ret}Engine eval: {}", format_score(evals[ply]));
+            if let Some(loss) = self.evaluation_loss(ply) {
+                if self.is_blunder(ply) {
+                    ret = format!("{ret}  ?? blunder, lost {loss:.2}");
+                }
+            }
+            ret = format!("{ret}\n");
+        }
+        if ply == self.record.len() {
+            if let Some(outcome) = self.record.outcome {
+                ret = format!("{ret}{outcome}\n");
+            }
+        }
+        ret
+    }
+
+    pub fn run_interactive(&mut self) {
+        //! Steps through the game reading commands from stdin until `q` is entered.
+        println!("{}", self.render());
+        loop {
+            println!("[enter/n] next, [p] previous, [f] first, [l] last, [<number>] go to ply, [e] explain score, [q] quit");
+            let mut input = String::new();
+            match io::stdin().read_line(&mut input) {
+                Ok(0) => break,
+                Ok(_) => {},
+                Err(_) => {
+                    println!("Failed to read line");
+                    continue;
+                }
+            }
+            match input.trim() {
+                "q" => break,
+                "" | "n" => { self.forward(); },
+                "p" => { self.backward(); },
+                "f" => self.go_to(0),
+                "l" => self.go_to(self.record.len()),
+                "e" => {
+                    match self.explain() {
+                        Some(explanation) => println!("{explanation}"),
+                        None => println!("no estimator to explain the score with"),
+                    }
+                    continue;
+                }
+                other => match other.parse::<usize>() {
+                    Ok(ply) => self.go_to(ply),
+                    Err(_) => {
+                        println!("unrecognised option \"{other}\"");
+                        continue;
+                    }
+                }
+            }
+            println!("{}", self.render());
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+pub fn derive_seed(seed: u64, index: u64) -> u64 {
+    //! Mixes a base seed with an index (splitmix64), so every job gets its own independent seed.
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub struct MatchScheduler {
+    concurrent_games: usize,
+    threads_per_game: usize,
+    seed: u64,
+}
+
+impl MatchScheduler {
+    pub fn new(concurrent_games: usize, threads_per_game: usize, seed: u64) -> Self {
+        assert!(concurrent_games > 0, "at least one game has to run at a time");
+        assert!(threads_per_game > 0, "every game needs at least one thread");
+        Self { concurrent_games, threads_per_game, seed }
+    }
+
+    pub fn sequential(seed: u64) -> Self {
+        //! One game at a time using all available threads for the search.
+        Self::new(1, rayon::current_num_threads(), seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn round(&self, index: u64) -> Self {
+        //! Same concurrency with a seed derived for the given round, so every round plays different games.
+        Self { seed: derive_seed(self.seed, index), ..*self }
+    }
+
+    pub fn job_seed(&self, index: usize) -> u64 {
+        derive_seed(self.seed, index as u64)
+    }
+
+    fn build_pool(threads: usize) -> ThreadPool {
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to build thread pool")
+    }
+
+    pub fn run<J, R, F>(&self, jobs: Vec<J>, play: F) -> Vec<R>
+    where
+        J: Send,
+        R: Send,
+        F: Fn(usize, J, u64) -> R + Sync,
+    {
+        //! Runs `play(index, job, seed)` for every job, at most `concurrent_games` at once. Each running job
+        //! gets its own pool of `threads_per_game` threads for the bots' internal parallelism.
+        //! Results are returned in job order, independent of the order in which the jobs finish.
+        let num_jobs = jobs.len();
+        let jobs: Vec<Mutex<Option<J>>> = jobs.into_iter().map(|job| Mutex::new(Some(job))).collect();
+        let results: Vec<Mutex<Option<R>>> = (0..num_jobs).map(|_| Mutex::new(None)).collect();
+        let next_job = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..self.concurrent_games.min(num_jobs) {
+                scope.spawn(|| {
+                    let pool = Self::build_pool(self.threads_per_game);
+                    loop {
+                        let index = next_job.fetch_add(1, Ordering::Relaxed);
+                        if index >= num_jobs {
+                            break;
+                        }
+                        let job = jobs[index].lock().unwrap().take().expect("every job is taken once");
+                        let result = pool.install(|| play(index, job, self.job_seed(index)));
+                        *results[index].lock().unwrap() = Some(result);
+                    }
+                });
+            }
+        });
+        results
+            .into_iter()
+            .map(|result| result.into_inner().unwrap().expect("every job should produce a result"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::controller::CheckersController;
+    use crate::game::Game;
+    use crate::game::estimators::CountEstimator;
+    use crate::game::player::{AlphaBetaBot, DummyBot};
+    use crate::observer::NullObserver;
+    use crate::record::GameOutcome;
+
+    fn play_round(scheduler: &MatchScheduler) -> Vec<(Vec<Board>, Option<GameOutcome>)> {
+        scheduler.run(vec![(); 4], |_, _, seed| {
+            let mut game = Game::new(
+                CheckersController::new(Board::default()),
+                Box::new(DummyBot::new()),
+                Box::new(AlphaBetaBot::new(CountEstimator::new(1.0, 3.0), 4)),
+            );
+            game.set_observer(Box::new(NullObserver));
+            game.set_seed(seed);
+            game.set_move_limit(40);
+            game.run();
+            let record = game.into_record();
+            (record.plies.iter().map(|ply| ply.board).collect(), record.outcome)
+        })
+    }
+
+    #[test]
+    fn games_do_not_depend_on_concurrency() {
+        let sequential = play_round(&MatchScheduler::new(1, 1, 11));
+        assert_eq!(sequential, play_round(&MatchScheduler::new(2, 2, 11)));
+        assert_eq!(sequential, play_round(&MatchScheduler::new(4, 3, 11)));
+        assert_ne!(sequential[0], sequential[1], "every game gets its own seed");
+        assert_ne!(sequential, play_round(&MatchScheduler::new(1, 1, 12)));
+    }
+}
@@ -0,0 +1,576 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rayon::prelude::*;
+use crate::controller::{CheckersAction, CheckersColor, CheckersController};
+use crate::game::estimators::{BoardEstimator, Position};
+use crate::stats::SearchStats;
+use crate::tablebase::{Tablebase, TablebaseResult};
+use crate::tt::{position_key, Bound, TranspositionTable, TtEntry};
+
+pub const MIN_SCORE: f64 = -1e10;
+pub const MAX_SCORE: f64 = 1e10;
+/// Scores within this distance of `MAX_SCORE` or `MIN_SCORE` are forced wins or losses.
+const MAX_WIN_DISTANCE: f64 = 1e6;
+
+pub fn win_distance(score: f64) -> Option<u32> {
+    //! Number of plies until the game ends if `score` is a forced win or loss, `None` for estimated scores.
+    (score.abs() > MAX_SCORE - MAX_WIN_DISTANCE).then(|| (MAX_SCORE - score.abs()).round() as u32)
+}
+
+pub fn format_score(score: f64) -> String {
+    //! `win in N` or `loss in N` (N counting plies) for forced results, otherwise the score itself.
+    match win_distance(score) {
+        Some(distance) if score > 0.0 => format!("win in {distance}"),
+        Some(distance) => format!("loss in {distance}"),
+        None => format!("{score:.3}"),
+    }
+}
+
+fn loss_in(plies: usize) -> f64 {
+    //! Score of a position lost after `plies` plies, higher the longer the loss takes so the losing side
+    //! resists as long as possible and the winning side takes the fastest win.
+    MIN_SCORE + plies as f64
+}
+
+fn to_tt(score: f64, ply: usize) -> f64 {
+    //! Win and loss scores count plies from the root, the table stores them counted from the position itself
+    //! so they stay correct when the position is reached at a different ply.
+    match win_distance(score) {
+        Some(_) => score + score.signum() * ply as f64,
+        None => score,
+    }
+}
+
+fn from_tt(score: f64, ply: usize) -> f64 {
+    match win_distance(score) {
+        Some(_) => score - score.signum() * ply as f64,
+        None => score,
+    }
+}
+
+/// How the root actions are spread over threads.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RootSearch {
+    /// Every action in parallel with its own window.
+    Independent,
+    /// The first action alone, the rest in parallel with the best score so far shared between the threads.
+    YoungBrothersWait,
+}
+
+/// Negamax search shared by the bots, which only decide how it is configured.
+///
+/// Scores inside the search are from the point of view of the side to move, everything returned to the
+/// outside is from white's point of view like `BoardEstimator::score`. Won and lost positions score
+/// `MAX_SCORE` and `MIN_SCORE` moved towards 0 by the number of plies from the root to the end of the game.
+pub struct SearchEngine<T> {
+    estimator: Arc<T>,
+    depth: usize,
+    pruning: bool,
+    pvs: bool,
+    aspiration_window: Option<f64>,
+    transposition_table: Option<Arc<TranspositionTable>>,
+    tablebase: Option<Arc<Tablebase>>,
+    root_search: RootSearch,
+    /// Once set the search returns right away without storing anything in the transposition table.
+    stop: Option<Arc<AtomicBool>>,
+}
+
+impl <T> Clone for SearchEngine<T> {
+    fn clone(&self) -> Self {
+        //! The clone shares the estimator, the transposition table and the tablebase.
+        Self {
+            estimator: self.estimator.clone(),
+            transposition_table: self.transposition_table.clone(),
+            tablebase: self.tablebase.clone(),
+            stop: self.stop.clone(),
+            ..*self
+        }
+    }
+}
+
+impl <T> SearchEngine<T> {
+    const NULL_WINDOW: f64 = 1e-3;
+    pub const ASPIRATION_WINDOW: f64 = 0.5;
+
+    pub fn new(estimator: T, depth: usize) -> Self {
+        //! Alpha-beta with PVS, a transposition table and Young Brothers Wait at the root.
+        Self {
+            estimator: Arc::new(estimator),
+            depth,
+            pruning: true,
+            pvs: true,
+            aspiration_window: None,
+            transposition_table: Some(Arc::new(TranspositionTable::new(TranspositionTable::DEFAULT_ENTRIES))),
+            tablebase: None,
+            root_search: RootSearch::YoungBrothersWait,
+            stop: None,
+        }
+    }
+
+    pub fn minimax(estimator: T, depth: usize) -> Self {
+        //! Plain minimax visiting every position up to `depth`.
+        Self::new(estimator, depth)
+            .with_pruning(false)
+            .with_pvs(false)
+            .with_transposition_table(None)
+            .with_root_search(RootSearch::Independent)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_pruning(mut self, enabled: bool) -> Self {
+        //! Alpha-beta pruning, without it all other options but the tablebase only cost time.
+        self.pruning = enabled;
+        self
+    }
+
+    pub fn with_pvs(mut self, enabled: bool) -> Self {
+        self.pvs = enabled;
+        self
+    }
+
+    pub fn with_aspiration_window(mut self, window: Option<f64>) -> Self {
+        //! Half width of the aspiration window around the score of a search two plies shallower, `None` searches
+        //! once with the full window.
+        self.aspiration_window = window;
+        self
+    }
+
+    pub fn with_transposition_table(mut self, table: Option<Arc<TranspositionTable>>) -> Self {
+        //! The table is shared by all search threads and kept between searches.
+        self.transposition_table = table;
+        self
+    }
+
+    pub fn with_tablebase(mut self, tablebase: Option<Arc<Tablebase>>) -> Self {
+        //! Positions covered by the tablebase are scored from it instead of being searched. An empty table is
+        //! not probed at all.
+        self.tablebase = tablebase.filter(|tablebase| !tablebase.is_empty());
+        self
+    }
+
+    pub fn with_root_search(mut self, root_search: RootSearch) -> Self {
+        self.root_search = root_search;
+        self
+    }
+
+    pub fn with_stop_flag(mut self, stop: Option<Arc<AtomicBool>>) -> Self {
+        //! Setting the flag aborts the search, its scores are meaningless from then on. Whatever was stored in the
+        //! transposition table before stays valid.
+        self.stop = stop;
+        self
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    pub fn predicted_reply(&self, controller: &CheckersController, side_to_move: CheckersColor) -> Option<CheckersController> {
+        //! The position after the action the transposition table holds as best for `side_to_move`, if it holds one.
+        let entry = self.transposition_table.as_ref()?.probe(position_key(controller, side_to_move))?;
+        let actions = controller.actions(side_to_move);
+        let mut reply = controller.clone();
+        reply.play(actions.get(entry.best as usize)?);
+        Some(reply)
+    }
+
+    fn probe_tablebase(&self, controller: &CheckersController, side_to_move: CheckersColor, ply: usize) -> Option<f64> {
+        //! The tables do not know the idle queen moves rule. A win or loss is only taken from them when the winner
+        //! can make all of its moves up to the end as idle queen moves without exceeding the limit, the loser
+        //! running out of idle moves can only end the game sooner. Draws are only taken without idle moves.
+        let idle_moves = |color| match color {
+            CheckersColor::White => controller.get_white_queen_idle_moves() as usize,
+            CheckersColor::Black => controller.get_black_queen_idle_moves() as usize,
+        };
+        let (winner, distance, score) = match self.tablebase.as_ref()?.probe(&controller.board, side_to_move)? {
+            TablebaseResult::Win(distance) => (side_to_move, distance as usize, -loss_in(ply + distance as usize)),
+            TablebaseResult::Loss(distance) => (side_to_move.opposite(), distance as usize, loss_in(ply + distance as usize)),
+            TablebaseResult::Draw => {
+                return (idle_moves(CheckersColor::White) == 0 && idle_moves(CheckersColor::Black) == 0).then_some(0.0);
+            }
+        };
+        // the winner moves every other ply and must not start a move with more than 8 idle moves
+        let winner_moves = (distance + usize::from(winner == side_to_move)) / 2;
+        (idle_moves(winner) + winner_moves <= 9).then_some(score)
+    }
+}
+
+fn sign(color: CheckersColor) -> f64 {
+    if color.is_white() { 1.0 } else { -1.0 }
+}
+
+fn child_positions(controller: &CheckersController, actions: &[CheckersAction]) -> Vec<CheckersController> {
+    actions
+        .iter()
+        .map(|action| {
+            let mut child = controller.clone();
+            child.play(action);
+            child
+        })
+        .collect()
+}
+
+impl <T: BoardEstimator + Send + Sync> SearchEngine<T> {
+    pub fn evaluate(&self, controller: &CheckersController, side_to_move: CheckersColor) -> f64 {
+        //! Searches the position and returns its score from white's point of view.
+        let score = self.negamax(controller, self.depth, 0, side_to_move, MIN_SCORE - 1.0, MAX_SCORE + 1.0, &mut SearchStats::default());
+        score * sign(side_to_move)
+    }
+
+    pub fn score_actions(&self, controller: &CheckersController, actions: &[CheckersAction], side_to_move: CheckersColor) -> (Vec<f64>, SearchStats) {
+        //! Scores every action from white's point of view. With Young Brothers Wait only the best actions get their
+        //! exact score, the others a bound showing they are worse.
+        //!
+        //! With an aspiration window the root is first searched two plies shallower, so the estimates of the same
+        //! side end the lines. The full depth search then tries the actions best first with a window around the
+        //! best shallow score. With Young Brothers Wait the whole root is searched again with the full window when
+        //! the best score lands outside of it, independent root actions are searched again one by one.
+        let started = Instant::now();
+        let children = child_positions(controller, actions);
+        let full_window = (MIN_SCORE - 1.0, MAX_SCORE + 1.0);
+        let mut stats = SearchStats { nodes: 1, interior_nodes: 1, ..SearchStats::default() };
+        let mut order: Vec<usize> = (0..children.len()).collect();
+        let scores = match self.aspiration_window {
+            None => self.search_root(&children, &order, self.depth, side_to_move, full_window, &mut stats),
+            Some(window) => {
+                let shallow = self.search_root(&children, &order, self.depth.saturating_sub(2).max(1), side_to_move, full_window, &mut stats);
+                order.sort_by(|&a, &b| shallow[b].total_cmp(&shallow[a]));
+                let guess = shallow[order[0]];
+                let (alpha, beta) = (guess - window, guess + window);
+                let scores = self.search_root(&children, &order, self.depth, side_to_move, (alpha, beta), &mut stats);
+                // independent root actions already searched themselves again when they left the window
+                let best = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                if self.root_search == RootSearch::YoungBrothersWait && (best <= alpha || best >= beta) {
+                    stats.re_searches += 1;
+                    self.search_root(&children, &order, self.depth, side_to_move, full_window, &mut stats)
+                } else {
+                    scores
+                }
+            }
+        };
+        stats.elapsed_us = started.elapsed().as_micros() as u64;
+        let scores = scores.into_iter().map(|score| score * sign(side_to_move)).collect();
+        (scores, stats)
+    }
+
+    fn search_root(
+        &self,
+        children: &[CheckersController],
+        order: &[usize],
+        depth: usize,
+        side_to_move: CheckersColor,
+        window: (f64, f64),
+        stats: &mut SearchStats,
+    ) -> Vec<f64> {
+        //! Scores of the root actions for the side to move at the root, searched `depth` plies deep in the given
+        //! order and returned in the order of `children`.
+        let search = |i: usize, shared: Option<&SharedBound>| (i, self.search_root_child(&children[i], depth, side_to_move, window, shared));
+        let results: Vec<(usize, (f64, SearchStats))> = match self.root_search {
+            RootSearch::Independent => order.par_iter().map(|&i| search(i, None)).collect(),
+            RootSearch::YoungBrothersWait => {
+                let shared = SharedBound::new();
+                let mut ret = vec![search(order[0], Some(&shared))];
+                ret.par_extend(order.par_iter().skip(1).map(|&i| search(i, Some(&shared))));
+                ret
+            }
+        };
+        let mut scores = vec![0.0; children.len()];
+        for (i, (score, child)) in results {
+            scores[i] = score;
+            *stats += child;
+        }
+        scores
+    }
+
+    fn search_root_child(
+        &self,
+        child: &CheckersController,
+        depth: usize,
+        side_to_move: CheckersColor,
+        (alpha, beta): (f64, f64),
+        shared: Option<&SharedBound>,
+    ) -> (f64, SearchStats) {
+        //! Score of the root action leading to `child` for the side to move at the root. Without a shared bound a
+        //! score outside of a narrowed window is searched again with the full one, so every action gets its exact
+        //! score.
+        //!
+        //! With a shared bound the action only proves whether it can match the best root action found so far;
+        //! worse actions get a score below the bound instead of their exact one.
+        let depth = depth.saturating_sub(1);
+        let mut stats = SearchStats::default();
+        let (full_alpha, full_beta) = (MIN_SCORE - 1.0, MAX_SCORE + 1.0);
+        let score = match shared {
+            Some(shared) => {
+                let alpha = shared.get().map_or(alpha, |best| alpha.max(best - Self::NULL_WINDOW));
+                let beta = beta.max(alpha + Self::NULL_WINDOW);
+                let score = -self.negamax(child, depth, 1, side_to_move.opposite(), -beta, -alpha, &mut stats);
+                shared.offer(score);
+                score
+            }
+            None => {
+                let mut score = -self.negamax(child, depth, 1, side_to_move.opposite(), -beta, -alpha, &mut stats);
+                if (score <= alpha && alpha > full_alpha) || (score >= beta && beta < full_beta) {
+                    stats.re_searches += 1;
+                    score = -self.negamax(child, depth, 1, side_to_move.opposite(), -full_beta, -full_alpha, &mut stats);
+                }
+                score
+            }
+        };
+        (score, stats)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &self,
+        controller: &CheckersController,
+        depth: usize,
+        ply: usize,
+        side_to_move: CheckersColor,
+        alpha: f64,
+        beta: f64,
+        stats: &mut SearchStats,
+    ) -> f64 {
+        if self.stopped() {
+            return 0.0;
+        }
+        stats.nodes += 1;
+        let idle_moves = match side_to_move {
+            CheckersColor::White => controller.get_white_queen_idle_moves(),
+            CheckersColor::Black => controller.get_black_queen_idle_moves(),
+        };
+        if idle_moves > 8 {
+            return loss_in(ply);
+        }
+        if let Some(score) = self.probe_tablebase(controller, side_to_move, ply) {
+            stats.tablebase_hits += 1;
+            return score;
+        }
+        if depth == 0 {
+            stats.leaf_evaluations += 1;
+            return self.estimator.score(&Position::new(controller, side_to_move)) * sign(side_to_move);
+        }
+        let key = self.transposition_table.as_ref().map(|_| position_key(controller, side_to_move));
+        let mut hint = None;
+        if let (Some(tt), Some(key)) = (&self.transposition_table, key) {
+            if let Some(entry) = tt.probe(key) {
+                // only results of the same depth are reused, so the scores do not depend on what other
+                // threads or earlier searches happened to store
+                let score = from_tt(entry.score, ply);
+                let usable = entry.depth as usize == depth && match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+                if usable {
+                    stats.tt_hits += 1;
+                    return score;
+                }
+                hint = Some(entry.best as usize);
+            }
+        }
+        let children = child_positions(controller, &controller.actions(side_to_move));
+        if children.is_empty() {
+            return loss_in(ply);
+        }
+        stats.interior_nodes += 1;
+        let (score, best) = self.search_children(&children, hint, depth, ply, side_to_move, alpha, beta, stats);
+        if let (Some(tt), Some(key), false) = (&self.transposition_table, key, self.stopped()) {
+            let bound = if score <= alpha {
+                Bound::Upper
+            } else if score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            tt.store(key, TtEntry { score: to_tt(score, ply), depth: depth as u8, bound, best: best as u8 });
+        }
+        score
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search_children(
+        &self,
+        children: &[CheckersController],
+        hint: Option<usize>,
+        depth: usize,
+        ply: usize,
+        side_to_move: CheckersColor,
+        mut alpha: f64,
+        beta: f64,
+        stats: &mut SearchStats,
+    ) -> (f64, usize) {
+        //! Fail-soft alpha-beta over the children, returns the score and the index of the best child. The child
+        //! suggested by the transposition table goes first. With PVS only the first child gets the full window,
+        //! the others are searched with a null window first and re-searched if they beat the best child so far.
+        let hint = hint.filter(|&i| i < children.len());
+        let order = hint.into_iter().chain((0..children.len()).filter(|&i| Some(i) != hint));
+        let mut current = f64::MIN;
+        let mut best = hint.unwrap_or(0);
+        let opponent = side_to_move.opposite();
+        for (n, i) in order.enumerate() {
+            let child = &children[i];
+            let est = if !self.pruning || !self.pvs || n == 0 {
+                -self.negamax(child, depth - 1, ply + 1, opponent, -beta, -alpha, stats)
+            } else {
+                let est = -self.negamax(child, depth - 1, ply + 1, opponent, -alpha - Self::NULL_WINDOW, -alpha, stats);
+                if est > alpha && est < beta {
+                    stats.re_searches += 1;
+                    -self.negamax(child, depth - 1, ply + 1, opponent, -beta, -alpha, stats)
+                } else {
+                    est
+                }
+            };
+            if est > current {
+                current = est;
+                best = i;
+            }
+            if !self.pruning {
+                continue;
+            }
+            alpha = alpha.max(est);
+            if alpha >= beta {
+                stats.cutoffs += 1;
+                break;
+            }
+        }
+        (current, best)
+    }
+}
+
+pub fn choose_best<R: Rng>(scores: &[f64], color: CheckersColor, rng: &mut R) -> (usize, f64) {
+    //! Picks one of the best scored actions for `color` at random, returns its index and score.
+    let best = scores
+        .iter()
+        .map(|&score| score * sign(color))
+        .max_by(|a, b| a.partial_cmp(b).unwrap())
+        .expect("there should be at least one score")
+        * sign(color);
+    let indices: Vec<usize> = scores
+        .iter()
+        .enumerate()
+        .filter(|(_, &score)| (score - best).abs() < f64::EPSILON)
+        .map(|(i, _)| i)
+        .collect();
+    (*indices.choose(rng).unwrap(), best)
+}
+
+/// Best root score found so far, shared between the threads searching the root actions.
+struct SharedBound {
+    bits: AtomicU64,
+}
+
+impl SharedBound {
+    const EMPTY: u64 = u64::MAX;
+
+    fn new() -> Self {
+        Self { bits: AtomicU64::new(Self::EMPTY) }
+    }
+
+    fn get(&self) -> Option<f64> {
+        let bits = self.bits.load(Ordering::Acquire);
+        (bits != Self::EMPTY).then(|| f64::from_bits(bits))
+    }
+
+    fn offer(&self, score: f64) {
+        let _ = self.bits.fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+            (bits == Self::EMPTY || score > f64::from_bits(bits)).then(|| score.to_bits())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::controller::Figure;
+    use crate::bench::bench_positions;
+    use crate::game::estimators::CountEstimator;
+
+    #[test]
+    fn table_scores_round_trip() {
+        for score in [0.0, -3.5, 12.25, MAX_SCORE - 7.0, MIN_SCORE + 2.0, MAX_SCORE, MIN_SCORE] {
+            for ply in [0, 1, 5, 40] {
+                assert_eq!(from_tt(to_tt(score, ply), ply), score, "score {score} at ply {ply}");
+            }
+        }
+        // a win 3 plies from the root stored by a node at ply 2 is a win in 1 from that node, and a win in 4 from
+        // the root when the same position comes up again at ply 3
+        let stored = to_tt(-loss_in(3), 2);
+        assert_eq!(win_distance(stored), Some(1));
+        assert_eq!(win_distance(from_tt(stored, 3)), Some(4));
+        assert_eq!(to_tt(0.75, 9), 0.75, "estimated scores are not shifted");
+    }
+
+    #[test]
+    fn shorter_win_is_preferred() {
+        // the queen on a7 wins the pawn on h4 in 3 plies by going to b8 or f2, every other move takes 5 plies
+        let mut board = Board::empty();
+        board.set(0, 6, Some(Figure::Queen(CheckersColor::White)));
+        board.set(7, 3, Some(Figure::Pawn(CheckersColor::Black)));
+        let engine = SearchEngine::new(CountEstimator::new(1.0, 3.0), 7).with_root_search(RootSearch::Independent);
+        let controller = CheckersController::new(board);
+        let actions = controller.actions(CheckersColor::White);
+        let (scores, _) = engine.score_actions(&controller, &actions, CheckersColor::White);
+        for (action, &score) in actions.iter().zip(&scores) {
+            let CheckersAction::Move(move_) = action else { panic!("no captures here") };
+            let expected = if ["A7 -> B8", "A7 -> F2"].contains(&move_.to_string().as_str()) { 3 } else { 5 };
+            assert_eq!(format_score(score), format!("win in {expected}"), "{move_}");
+        }
+        let (best, score) = choose_best(&scores, CheckersColor::White, &mut rand::thread_rng());
+        assert_eq!(win_distance(score), Some(3));
+        assert_eq!(win_distance(scores[best]), Some(3));
+        assert_eq!(engine.evaluate(&controller, CheckersColor::White), score);
+    }
+
+    #[test]
+    fn table_is_probed_within_the_idle_move_budget() {
+        // the queen on a7 wins the pawn on h4 in 3 plies, making 2 moves
+        let mut board = Board::empty();
+        board.set(0, 6, Some(Figure::Queen(CheckersColor::White)));
+        board.set(7, 3, Some(Figure::Pawn(CheckersColor::Black)));
+        let engine = SearchEngine::new(CountEstimator::new(1.0, 3.0), 5)
+            .with_tablebase(Some(Arc::new(Tablebase::generate(2))));
+
+        let controller = CheckersController::with_idle_moves(board, 5, 0);
+        let (scores, stats) = engine.score_actions(&controller, &controller.actions(CheckersColor::White), CheckersColor::White);
+        assert!(stats.tablebase_hits > 0, "the queen has made idle moves but can still win in time");
+        assert_eq!(format_score(scores.into_iter().fold(f64::MIN, f64::max)), "win in 3");
+
+        // with 8 idle moves the queen can move once more and loses before its second move
+        let controller = CheckersController::with_idle_moves(board, 8, 0);
+        assert_eq!(format_score(engine.evaluate(&controller, CheckersColor::White)), "loss in 2");
+    }
+
+    #[test]
+    fn aspiration_matches_the_full_window() {
+        let positions = bench_positions(4, 6, 0);
+        for root_search in [RootSearch::Independent, RootSearch::YoungBrothersWait] {
+            let full = SearchEngine::new(CountEstimator::new(1.0, 3.0), 6).with_root_search(root_search);
+            for window in [0.01, SearchEngine::<CountEstimator>::ASPIRATION_WINDOW] {
+                let aspiration = SearchEngine::new(CountEstimator::new(1.0, 3.0), 6)
+                    .with_root_search(root_search)
+                    .with_aspiration_window(Some(window));
+                for controller in &positions {
+                    let actions = controller.actions(CheckersColor::White);
+                    let (expected, _) = full.score_actions(controller, &actions, CheckersColor::White);
+                    let (scores, _) = aspiration.score_actions(controller, &actions, CheckersColor::White);
+                    let best = |scores: &[f64]| scores.iter().copied().fold(f64::MIN, f64::max);
+                    assert_eq!(best(&scores), best(&expected), "{root_search:?} with window {window}");
+                    if root_search == RootSearch::Independent {
+                        assert_eq!(scores, expected, "independent root actions keep their exact scores");
+                    }
+                }
+            }
+        }
+    }
+}
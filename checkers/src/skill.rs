@@ -0,0 +1,134 @@
+use std::str::FromStr;
+use rand::Rng;
+use crate::controller::CheckersColor;
+use crate::search::choose_best;
+
+/// Playing strength of `AlphaBetaBot`, from making obvious mistakes to full strength.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SkillLevel {
+    Beginner,
+    Casual,
+    Intermediate,
+    Advanced,
+    Master,
+}
+
+impl SkillLevel {
+    pub fn max_depth(&self) -> Option<usize> {
+        //! Search depth limit, `None` keeps the depth the bot was created with.
+        match self {
+            Self::Beginner => Some(2),
+            Self::Casual => Some(4),
+            Self::Intermediate => Some(6),
+            Self::Advanced => Some(8),
+            Self::Master => None,
+        }
+    }
+
+    pub fn temperature(&self) -> f64 {
+        //! Softmax temperature in units of the estimator's score (a pawn for `CountEstimator`).
+        match self {
+            Self::Beginner => 1.5,
+            Self::Casual => 0.8,
+            Self::Intermediate => 0.3,
+            Self::Advanced => 0.1,
+            Self::Master => 0.0,
+        }
+    }
+
+    pub fn blunder_probability(&self) -> f64 {
+        //! Probability of playing a random action that is not among the best ones.
+        match self {
+            Self::Beginner => 0.2,
+            Self::Casual => 0.1,
+            Self::Intermediate => 0.03,
+            Self::Advanced | Self::Master => 0.0,
+        }
+    }
+
+    pub fn needs_all_scores(&self) -> bool {
+        //! Whether the choice looks at the scores of all actions rather than only at the best ones.
+        self.temperature() > 0.0 || self.blunder_probability() > 0.0
+    }
+
+    pub fn choose<R: Rng>(&self, scores: &[f64], color: CheckersColor, rng: &mut R) -> (usize, f64) {
+        //! Picks an action for `color` from the scores (white's point of view) of all actions,
+        //! returns its index and score.
+        let (best, best_score) = choose_best(scores, color, rng);
+        let sign = if color.is_white() { 1.0 } else { -1.0 };
+        if rng.gen::<f64>() < self.blunder_probability() {
+            let worse: Vec<usize> = (0..scores.len()).filter(|&i| scores[i] * sign < best_score * sign).collect();
+            if !worse.is_empty() {
+                let i = worse[rng.gen_range(0..worse.len())];
+                return (i, scores[i]);
+            }
+        }
+        let temperature = self.temperature();
+        if temperature <= 0.0 {
+            return (best, best_score);
+        }
+        // softmax, shifted by the best score so decided positions do not overflow
+        let weights: Vec<f64> = scores
+            .iter()
+            .map(|&score| ((score - best_score) * sign / temperature).exp())
+            .collect();
+        let mut pick = rng.gen_range(0.0..weights.iter().sum::<f64>());
+        for (i, &weight) in weights.iter().enumerate() {
+            if pick < weight {
+                return (i, scores[i]);
+            }
+            pick -= weight;
+        }
+        (best, best_score)
+    }
+}
+
+impl FromStr for SkillLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "beginner" => Ok(Self::Beginner),
+            "casual" => Ok(Self::Casual),
+            "intermediate" => Ok(Self::Intermediate),
+            "advanced" => Ok(Self::Advanced),
+            "master" => Ok(Self::Master),
+            _ => Err(format!("unknown skill level {s:?}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const SCORES: [f64; 4] = [0.5, 2.0, -1.0, 1.9];
+
+    fn picks(level: SkillLevel, color: CheckersColor, seed: u64) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..500).map(|_| level.choose(&SCORES, color, &mut rng).0).collect()
+    }
+
+    #[test]
+    fn master_plays_the_best_move() {
+        assert!(picks(SkillLevel::Master, CheckersColor::White, 1).iter().all(|&i| i == 1));
+        assert!(picks(SkillLevel::Master, CheckersColor::Black, 1).iter().all(|&i| i == 2));
+        assert!(!SkillLevel::Master.needs_all_scores());
+    }
+
+    #[test]
+    fn low_levels_are_seeded() {
+        let beginner = picks(SkillLevel::Beginner, CheckersColor::White, 7);
+        assert_eq!(beginner, picks(SkillLevel::Beginner, CheckersColor::White, 7));
+        assert_ne!(beginner, picks(SkillLevel::Beginner, CheckersColor::White, 8));
+        assert!(beginner.iter().any(|&i| i != 1), "beginners play other moves than the best one");
+        assert!(beginner.contains(&2), "beginners even play the worst move now and then");
+
+        // the close second best move is played often at a low temperature, the bad ones hardly
+        let casual = picks(SkillLevel::Casual, CheckersColor::White, 7);
+        let count = |i: usize| casual.iter().filter(|&&pick| pick == i).count();
+        assert!(count(1) > count(3) && count(3) > count(0) && count(0) > count(2));
+    }
+}
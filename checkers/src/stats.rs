@@ -0,0 +1,68 @@
+use std::fmt::{Display, Formatter};
+use std::ops::AddAssign;
+use serde::{Deserialize, Serialize};
+
+/// Counters of a single search. Every search thread fills its own copy, the copies are merged afterwards.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchStats {
+    /// Positions visited, the root included.
+    pub nodes: u64,
+    /// Positions whose actions were generated and searched.
+    pub interior_nodes: u64,
+    pub leaf_evaluations: u64,
+    pub cutoffs: u64,
+    /// Searches repeated with a wider window after a null window or aspiration window failed.
+    pub re_searches: u64,
+    pub tt_hits: u64,
+    pub tablebase_hits: u64,
+    /// Searches of a position that was already searched in the background while the opponent was thinking.
+    pub ponder_hits: u64,
+    pub elapsed_us: u64,
+}
+
+impl SearchStats {
+    pub fn branching_factor(&self) -> f64 {
+        //! Average number of children searched per interior node.
+        if self.interior_nodes == 0 {
+            return 0.0;
+        }
+        self.nodes.saturating_sub(1) as f64 / self.interior_nodes as f64
+    }
+
+    pub fn nodes_per_second(&self) -> f64 {
+        if self.elapsed_us == 0 {
+            return 0.0;
+        }
+        self.nodes as f64 * 1e6 / self.elapsed_us as f64
+    }
+}
+
+impl AddAssign for SearchStats {
+    fn add_assign(&mut self, other: Self) {
+        //! Merges the counters, the elapsed time is kept since threads run at the same time.
+        self.nodes += other.nodes;
+        self.interior_nodes += other.interior_nodes;
+        self.leaf_evaluations += other.leaf_evaluations;
+        self.cutoffs += other.cutoffs;
+        self.re_searches += other.re_searches;
+        self.tt_hits += other.tt_hits;
+        self.tablebase_hits += other.tablebase_hits;
+        self.ponder_hits += other.ponder_hits;
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "nodes: {}, leaves: {}, cutoffs: {}, re-searches: {}, tt hits: {}, tablebase hits: {}, branching: {:.2}, {:.0} nodes/s",
+            self.nodes, self.leaf_evaluations, self.cutoffs, self.re_searches, self.tt_hits, self.tablebase_hits,
+            self.branching_factor(), self.nodes_per_second()
+        )?;
+        if self.ponder_hits > 0 {
+            write!(f, ", ponder hits: {}", self.ponder_hits)?;
+        }
+        Ok(())
+    }
+}
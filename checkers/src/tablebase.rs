@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use rayon::prelude::*;
+use crate::board::Board;
+use crate::controller::{CheckersColor, CheckersController, Figure};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TablebaseResult {
+    /// The side to move wins in the given number of plies.
+    Win(u16),
+    /// The side to move loses in the given number of plies.
+    Loss(u16),
+    Draw,
+}
+
+fn position_key(board: &Board, side_to_move: CheckersColor) -> u128 {
+    let side = if side_to_move.is_white() { 0 } else { 1 };
+    board.occupation as u128
+        | ((board.occupation & board.color) as u128) << 32
+        | ((board.occupation & board.figure) as u128) << 64
+        | side << 96
+}
+
+fn board_from_key(key: u128) -> (Board, CheckersColor) {
+    let board = Board {
+        occupation: key as u32,
+        color: (key >> 32) as u32,
+        figure: (key >> 64) as u32,
+        flags: 0,
+    };
+    let side = if (key >> 96) & 1 == 0 { CheckersColor::White } else { CheckersColor::Black };
+    (board, side)
+}
+
+fn square_coords(square: usize) -> (u8, u8) {
+    //! The 32 playable squares in the order of `Board`'s bits.
+    let y = (square / 4) as u8;
+    let x = 2 * (square % 4) as u8 + y % 2;
+    (x, y)
+}
+
+const FIGURES: [Figure; 4] = [
+    Figure::Pawn(CheckersColor::White),
+    Figure::Queen(CheckersColor::White),
+    Figure::Pawn(CheckersColor::Black),
+    Figure::Queen(CheckersColor::Black),
+];
+
+/// Win/draw/loss and distance to win for every position with up to `max_pieces` pieces.
+///
+/// The rule about idle queen moves is not taken into account, positions are scored as if it did not exist.
+/// On disk the table starts with the magic `CKTB`, a version byte, the piece count and the number of entries,
+/// followed by the positions (occupation, color and figure as little endian `u32`, side to move as a byte and the
+/// result as `i16`: `d` for a win in `d` plies, `-d - 1` for a loss in `d` plies and 0 for a draw).
+pub struct Tablebase {
+    max_pieces: u32,
+    entries: HashMap<u128, i16>,
+}
+
+impl Tablebase {
+    const MAGIC: &'static [u8; 4] = b"CKTB";
+    const VERSION: u8 = 2;
+
+    pub fn max_pieces(&self) -> u32 {
+        self.max_pieces
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn covers(&self, board: &Board) -> bool {
+        board.occupation.count_ones() <= self.max_pieces
+    }
+
+    pub fn probe(&self, board: &Board, side_to_move: CheckersColor) -> Option<TablebaseResult> {
+        //! `None` for positions the table does not cover or does not contain, e.g. with unpromoted pawns on the
+        //! last row.
+        if !self.covers(board) {
+            return None;
+        }
+        if board.num_figures(side_to_move) == 0 {
+            return Some(TablebaseResult::Loss(0));
+        }
+        Some(match *self.entries.get(&position_key(board, side_to_move))? {
+            0 => TablebaseResult::Draw,
+            value if value > 0 => TablebaseResult::Win(value as u16),
+            value => TablebaseResult::Loss((-value - 1) as u16),
+        })
+    }
+
+    fn enumerate(max_pieces: u32) -> Vec<u128> {
+        fn place(square: usize, left: u32, board: &mut Board, ret: &mut Vec<u128>) {
+            if left > 0 {
+                for next in square..32 {
+                    let (x, y) = square_coords(next);
+                    for figure in FIGURES {
+                        // pawns standing on their promotion row would already be queens
+                        match figure {
+                            Figure::Pawn(CheckersColor::White) if y == 7 => continue,
+                            Figure::Pawn(CheckersColor::Black) if y == 0 => continue,
+                            _ => {}
+                        }
+                        board.set(x, y, Some(figure));
+                        place(next + 1, left - 1, board, ret);
+                        board.set(x, y, None);
+                    }
+                }
+                return;
+            }
+            if board.num_white_figures() > 0 && board.num_black_figures() > 0 {
+                ret.push(position_key(board, CheckersColor::White));
+                ret.push(position_key(board, CheckersColor::Black));
+            }
+        }
+        let mut ret = Vec::new();
+        for pieces in 2..=max_pieces {
+            place(0, pieces, &mut Board::empty(), &mut ret);
+        }
+        ret
+    }
+
+    pub fn generate(max_pieces: u32) -> Self {
+        //! Retrograde analysis by iterating to a fixed point: a position is won in `d` plies if some action leads to
+        //! a position lost in `d - 1` plies, and lost if every action leads to a won position.
+        let keys = Self::enumerate(max_pieces);
+        let index: HashMap<u128, u32> = keys.iter().enumerate().map(|(i, &key)| (key, i as u32)).collect();
+
+        // successors of every position, `None` when an action captures the last enemy piece
+        let successors: Vec<Option<Vec<u32>>> = keys
+            .par_iter()
+            .map(|&key| {
+                let (board, side) = board_from_key(key);
+                let mut ret = Vec::new();
+                for action in CheckersController::new(board).actions(side) {
+                    let mut controller = CheckersController::new(board);
+                    controller.play(&action);
+                    if controller.board.num_figures(side.opposite()) == 0 {
+                        return None;
+                    }
+                    ret.push(index[&position_key(&controller.board, side.opposite())]);
+                }
+                Some(ret)
+            })
+            .collect();
+
+        let mut values: Vec<i16> = successors
+            .iter()
+            .map(|successors| match successors {
+                None => 1,
+                Some(successors) if successors.is_empty() => -1,
+                Some(_) => 0,
+            })
+            .collect();
+        loop {
+            let updates: Vec<(usize, i16)> = successors
+                .par_iter()
+                .enumerate()
+                .filter(|&(i, _)| values[i] == 0)
+                .filter_map(|(i, successors)| {
+                    let successors = successors.as_ref()?;
+                    let fastest_win = successors.iter()
+                        .map(|&s| values[s as usize])
+                        .filter(|&value| value < 0)
+                        .map(|value| -value)
+                        .min();
+                    if let Some(distance) = fastest_win {
+                        return Some((i, distance));
+                    }
+                    if successors.iter().all(|&s| values[s as usize] > 0) {
+                        let slowest_loss = successors.iter().map(|&s| values[s as usize]).max().unwrap();
+                        return Some((i, -slowest_loss - 2));
+                    }
+                    None
+                })
+                .collect();
+            if updates.is_empty() {
+                break;
+            }
+            for (i, value) in updates {
+                values[i] = value;
+            }
+        }
+
+        let entries = keys.into_iter().zip(values).collect();
+        Self { max_pieces, entries }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut keys: Vec<&u128> = self.entries.keys().collect();
+        keys.sort();
+        let mut ret = Vec::with_capacity(10 + keys.len() * 15);
+        ret.extend_from_slice(Self::MAGIC);
+        ret.push(Self::VERSION);
+        ret.push(self.max_pieces as u8);
+        ret.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        for &key in keys {
+            let (board, side) = board_from_key(key);
+            ret.extend_from_slice(&board.occupation.to_le_bytes());
+            ret.extend_from_slice(&board.color.to_le_bytes());
+            ret.extend_from_slice(&board.figure.to_le_bytes());
+            ret.push(if side.is_white() { 0 } else { 1 });
+            ret.extend_from_slice(&self.entries[&key].to_le_bytes());
+        }
+        ret
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        if bytes.len() < 10 || &bytes[0..4] != Self::MAGIC {
+            return Err(invalid("not a tablebase file"));
+        }
+        if bytes[4] != Self::VERSION {
+            return Err(invalid("unsupported tablebase version"));
+        }
+        let max_pieces = bytes[5] as u32;
+        let count = u32::from_le_bytes(bytes[6..10].try_into().unwrap()) as usize;
+        let body = &bytes[10..];
+        if body.len() != count * 15 {
+            return Err(invalid("truncated tablebase file"));
+        }
+        let u32_at = |chunk: &[u8], at: usize| u32::from_le_bytes(chunk[at..at + 4].try_into().unwrap());
+        let entries = body
+            .chunks_exact(15)
+            .map(|chunk| {
+                let board = Board { occupation: u32_at(chunk, 0), color: u32_at(chunk, 4), figure: u32_at(chunk, 8), flags: 0 };
+                let side = if chunk[12] == 0 { CheckersColor::White } else { CheckersColor::Black };
+                let value = i16::from_le_bytes([chunk[13], chunk[14]]);
+                (position_key(&board, side), value)
+            })
+            .collect();
+        Ok(Self { max_pieces, entries })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::estimators::CountEstimator;
+    use std::sync::Arc;
+    use crate::search::{win_distance, SearchEngine};
+
+    const WHITE_QUEEN: Figure = Figure::Queen(CheckersColor::White);
+    const BLACK_PAWN: Figure = Figure::Pawn(CheckersColor::Black);
+
+    fn board(pieces: &[(u8, u8, Figure)]) -> Board {
+        let mut board = Board::empty();
+        for &(x, y, figure) in pieces {
+            board.set(x, y, Some(figure));
+        }
+        board
+    }
+
+    #[test]
+    fn queen_against_pawn() {
+        let tablebase = Tablebase::generate(2);
+        // the queen on c5 takes the pawn on f2 from across the board
+        let board_ = board(&[(2, 4, WHITE_QUEEN), (5, 1, BLACK_PAWN)]);
+        assert_eq!(tablebase.probe(&board_, CheckersColor::White), Some(TablebaseResult::Win(1)));
+        // the pawn on f4 takes the queen on e3
+        let board_ = board(&[(4, 2, WHITE_QUEEN), (5, 3, BLACK_PAWN)]);
+        assert_eq!(tablebase.probe(&board_, CheckersColor::Black), Some(TablebaseResult::Win(1)));
+        // the pawn on h2 is blocked by the queen on g1
+        let board_ = board(&[(6, 0, WHITE_QUEEN), (7, 1, BLACK_PAWN)]);
+        assert_eq!(tablebase.probe(&board_, CheckersColor::Black), Some(TablebaseResult::Loss(0)));
+        // the pawn on d8 has to step next to the queen on d6 and is taken
+        let board_ = board(&[(3, 5, WHITE_QUEEN), (3, 7, BLACK_PAWN)]);
+        assert_eq!(tablebase.probe(&board_, CheckersColor::Black), Some(TablebaseResult::Loss(2)));
+    }
+
+    #[test]
+    fn probe_misses_positions_outside_the_table() {
+        let tablebase = Tablebase::generate(2);
+        let unpromoted = board(&[(3, 5, WHITE_QUEEN), (2, 0, BLACK_PAWN)]);
+        assert_eq!(tablebase.probe(&unpromoted, CheckersColor::White), None);
+        let three_pieces = board(&[(3, 5, WHITE_QUEEN), (3, 7, BLACK_PAWN), (5, 7, BLACK_PAWN)]);
+        assert_eq!(tablebase.probe(&three_pieces, CheckersColor::White), None);
+    }
+
+    #[test]
+    fn distances_follow_from_the_successors() {
+        //! A win in `d` has an action into a loss in `d - 1` and none into a shorter loss, a loss in `d` only has
+        //! actions into wins and the slowest of them takes `d - 1` plies.
+        let tablebase = Tablebase::generate(2);
+        for &key in tablebase.entries.keys() {
+            let (board, side) = board_from_key(key);
+            let successors: Vec<Option<TablebaseResult>> = CheckersController::new(board).actions(side)
+                .iter()
+                .map(|action| {
+                    let mut controller = CheckersController::new(board);
+                    controller.play(action);
+                    tablebase.probe(&controller.board, side.opposite())
+                })
+                .collect();
+            let losses = successors.iter().filter_map(|&result| match result {
+                Some(TablebaseResult::Loss(distance)) => Some(distance + 1),
+                _ => None,
+            });
+            let wins = successors.iter().filter_map(|&result| match result {
+                Some(TablebaseResult::Win(distance)) => Some(distance + 1),
+                _ => None,
+            });
+            match tablebase.probe(&board, side).unwrap() {
+                TablebaseResult::Win(distance) => assert_eq!(losses.min(), Some(distance), "{board}"),
+                TablebaseResult::Loss(distance) => {
+                    assert_eq!(wins.clone().count(), successors.len(), "{board}");
+                    assert_eq!(wins.max().unwrap_or(0), distance, "{board}");
+                }
+                TablebaseResult::Draw => {
+                    assert_eq!(losses.count(), 0, "{board}");
+                    assert!(wins.count() < successors.len(), "{board}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn search_agrees_with_the_table() {
+        //! Searching deep enough finds every result the table knows about, with the same distance. Positions at the
+        //! search horizon are estimated, so only results shorter than the depth are proven.
+        const DEPTH: usize = 6;
+        let tablebase = Arc::new(Tablebase::generate(3));
+        let plain = SearchEngine::new(CountEstimator::new(1.0, 3.0), DEPTH);
+        let probing = SearchEngine::new(CountEstimator::new(1.0, 3.0), DEPTH).with_tablebase(Some(tablebase.clone()));
+        let mut keys: Vec<u128> = tablebase.entries.keys().copied().collect();
+        keys.sort();
+        for &key in keys.iter().step_by(997) {
+            let (board, side) = board_from_key(key);
+            let sign = if side.is_white() { 1.0 } else { -1.0 };
+            let controller = CheckersController::new(board);
+            let expected = match tablebase.probe(&board, side).unwrap() {
+                TablebaseResult::Win(distance) if (distance as usize) < DEPTH => Some((distance as u32, true)),
+                TablebaseResult::Loss(distance) if (distance as usize) < DEPTH => Some((distance as u32, false)),
+                _ => None,
+            };
+            for engine in [&plain, &probing] {
+                let score = engine.evaluate(&controller, side) * sign;
+                let found = win_distance(score).filter(|&distance| (distance as usize) < DEPTH).map(|distance| (distance, score > 0.0));
+                assert_eq!(found, expected, "{side:?} to move\n{board}");
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::board::Board;
+use crate::controller::{CheckersColor, CheckersController};
+use crate::game::estimators::Position;
+use crate::learn::sigmoid;
+use crate::pdn::{fen, parse_fen, PdnError, PdnGame};
+use crate::tune::Tunable;
+
+/// A position with the result of the game it was taken from.
+pub struct LabeledPosition {
+    pub board: Board,
+    pub side_to_move: CheckersColor,
+    /// 1 for a white win, 0.5 for a draw, 0 for a black win.
+    pub result: f64,
+}
+
+impl LabeledPosition {
+    pub fn is_quiet(&self) -> bool {
+        //! Neither side can capture, so the static score is not about to be overturned by an exchange.
+        let controller = CheckersController::new(self.board);
+        controller.options(self.side_to_move).0.is_empty()
+            && controller.options(self.side_to_move.opposite()).0.is_empty()
+    }
+}
+
+pub fn parse_positions(text: &str) -> Result<Vec<LabeledPosition>, PdnError> {
+    //! One `<FEN>;<result>` per line, the result being `1-0`, `0-1`, `1/2-1/2` or a number from 0 to 1.
+    let mut ret = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (position, result) = line.split_once(';')
+            .ok_or_else(|| PdnError::new(format!("missing result in {line:?}")))?;
+        let (board, side_to_move) = parse_fen(position)?;
+        let result = match result.trim() {
+            "1-0" => 1.0,
+            "0-1" => 0.0,
+            "1/2-1/2" => 0.5,
+            other => other.parse::<f64>().ok()
+                .filter(|result| (0.0..=1.0).contains(result))
+                .ok_or_else(|| PdnError::new(format!("invalid result {other:?} in {line:?}")))?,
+        };
+        ret.push(LabeledPosition { board, side_to_move, result });
+    }
+    Ok(ret)
+}
+
+pub fn write_positions(positions: &[LabeledPosition]) -> String {
+    positions
+        .iter()
+        .map(|position| format!("{};{}\n", fen(&position.board, position.side_to_move), position.result))
+        .collect()
+}
+
+pub fn labeled_positions(game: &PdnGame) -> Vec<LabeledPosition> {
+    //! Every position of a game with a known result, none for unfinished games.
+    let Some(winner) = game.result else { return Vec::new() };
+    let result = match winner {
+        Some(CheckersColor::White) => 1.0,
+        Some(CheckersColor::Black) => 0.0,
+        None => 0.5,
+    };
+    game.positions()
+        .into_iter()
+        .map(|(controller, side_to_move)| LabeledPosition { board: controller.board, side_to_move, result })
+        .collect()
+}
+
+/// Texel tuning: minimizes the mean squared error between the game results and `sigmoid(scale * score)`
+/// over quiet positions by changing one parameter at a time.
+pub struct TexelTuner {
+    step: f64,
+    min_step: f64,
+    max_passes: usize,
+}
+
+impl Default for TexelTuner {
+    fn default() -> Self {
+        Self { step: 0.1, min_step: 0.01, max_passes: 100 }
+    }
+}
+
+impl TexelTuner {
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self.min_step = step / 10.0;
+        self
+    }
+
+    pub fn with_max_passes(mut self, passes: usize) -> Self {
+        self.max_passes = passes;
+        self
+    }
+
+    pub fn error<T: Tunable>(estimator: &T, positions: &[LabeledPosition], scale: f64) -> f64 {
+        let total: f64 = positions
+            .iter()
+            .map(|position| (position.result - sigmoid(scale * estimator.score(&Position::from_board(position.board, position.side_to_move)))).powi(2))
+            .sum();
+        total / positions.len().max(1) as f64
+    }
+
+    pub fn fit_scale<T: Tunable>(estimator: &T, positions: &[LabeledPosition]) -> f64 {
+        //! The scale mapping the untuned scores best to results, kept fixed while tuning so that the
+        //! parameters do not just grow or shrink together.
+        let mut scale = 1.0;
+        let mut step = 0.5;
+        let mut error = Self::error(estimator, positions, scale);
+        while step > 1e-3 {
+            let candidates = [scale + step, (scale - step).max(1e-3)];
+            match candidates.into_iter().map(|s| (s, Self::error(estimator, positions, s))).find(|&(_, e)| e < error) {
+                Some((better, better_error)) => (scale, error) = (better, better_error),
+                None => step /= 2.0,
+            }
+        }
+        scale
+    }
+
+    pub fn tune<T: Tunable>(&self, estimator: T, positions: &[LabeledPosition], mut log: impl FnMut(usize, f64)) -> T {
+        //! Tries moving every parameter up and down by the step and keeps changes lowering the error,
+        //! halving the step when a whole pass finds none. `log` gets the error after every pass.
+        let scale = Self::fit_scale(&estimator, positions);
+        let mut parameters = estimator.parameters();
+        let mut error = Self::error(&estimator, positions, scale);
+        let mut step = self.step;
+        for pass in 1..=self.max_passes {
+            let mut improved = false;
+            for i in 0..parameters.len() {
+                for delta in [step, -step] {
+                    let mut candidate = parameters.clone();
+                    candidate[i] += delta;
+                    let candidate_error = Self::error(&estimator.with_parameters(&candidate), positions, scale);
+                    if candidate_error < error {
+                        parameters = candidate;
+                        error = candidate_error;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+            log(pass, error);
+            if !improved {
+                step /= 2.0;
+                if step < self.min_step {
+                    break;
+                }
+            }
+        }
+        estimator.with_parameters(&parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::estimators::CountEstimator;
+
+    const POSITIONS: &str = "W:Wa1,c3:Bh8;1-0\nB:Wa1:Bf6,h8;0-1\nW:Wa1:Bh8;1/2-1/2\n";
+
+    #[test]
+    fn positions_round_trip() {
+        let positions = parse_positions(POSITIONS).unwrap();
+        assert_eq!(positions.iter().map(|position| position.result).collect::<Vec<_>>(), [1.0, 0.0, 0.5]);
+        assert_eq!(write_positions(&positions), POSITIONS.replace("1-0", "1").replace("0-1", "0").replace("1/2-1/2", "0.5"));
+        assert!(parse_positions("W:Wa1:Bh8;2").is_err());
+    }
+
+    #[test]
+    fn one_pass_lowers_the_error() {
+        // material decides every game, but the untuned estimator gives it no weight
+        let positions = parse_positions(POSITIONS).unwrap();
+        let untuned = CountEstimator::new(0.0, 0.0);
+        let before = TexelTuner::error(&untuned, &positions, 1.0);
+        let mut errors = Vec::new();
+        let tuned = TexelTuner::default().with_max_passes(1).tune(untuned, &positions, |_, error| errors.push(error));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0] < before, "{} is not below {before}", errors[0]);
+        assert!(tuned.parameters()[0] > 0.0);
+    }
+}
@@ -0,0 +1,361 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use crate::board::Board;
+use crate::controller::{CheckersColor, CheckersController};
+use crate::game::Game;
+use crate::game::player::Player;
+use crate::observer::NullObserver;
+use crate::record::GameOutcome;
+use crate::scheduler::MatchScheduler;
+
+pub struct Entrant {
+    pub name: String,
+    factory: Box<dyn Fn() -> Box<dyn Player> + Send + Sync>,
+}
+
+impl Entrant {
+    pub fn new(name: &str, factory: impl Fn() -> Box<dyn Player> + Send + Sync + 'static) -> Self {
+        Self { name: String::from(name), factory: Box::new(factory) }
+    }
+
+    pub fn create(&self) -> Box<dyn Player> {
+        (self.factory)()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TournamentFormat {
+    /// Every entrant plays every other entrant.
+    RoundRobin,
+    /// The first entrant plays every other entrant, the others do not play each other.
+    Gauntlet,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatchResult {
+    pub white: usize,
+    pub black: usize,
+    pub outcome: GameOutcome,
+    pub plies: usize,
+}
+
+impl MatchResult {
+    pub fn score_of(&self, entrant: usize) -> Option<f64> {
+        //! 1 for a win, 0.5 for a draw, 0 for a loss, `None` if the entrant did not play this game.
+        let color = if entrant == self.white {
+            CheckersColor::White
+        } else if entrant == self.black {
+            CheckersColor::Black
+        } else {
+            return None;
+        };
+        Some(match self.outcome.winner {
+            Some(winner) if winner == color => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        })
+    }
+}
+
+pub fn random_opening(plies: usize, rng: &mut impl Rng) -> CheckersController {
+    //! Plays `plies` random actions from the starting position, stopping early if a side runs out of actions.
+    let mut controller = CheckersController::new(Board::default());
+    let mut color = CheckersColor::White;
+    for _ in 0..plies {
+        let Some(action) = controller.actions(color).choose(rng).cloned() else {
+            break;
+        };
+        controller.play(&action);
+        color = color.opposite();
+    }
+    controller
+}
+
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    format: TournamentFormat,
+    games_per_pairing: usize,
+    opening_plies: usize,
+    move_limit: usize,
+    scheduler: MatchScheduler,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>, format: TournamentFormat) -> Self {
+        Self {
+            entrants,
+            format,
+            games_per_pairing: 2,
+            opening_plies: 4,
+            move_limit: 300,
+            scheduler: MatchScheduler::sequential(rand::random()),
+        }
+    }
+
+    pub fn with_scheduler(mut self, scheduler: MatchScheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn with_games_per_pairing(mut self, games: usize) -> Self {
+        self.games_per_pairing = games;
+        self
+    }
+
+    pub fn with_opening_plies(mut self, plies: usize) -> Self {
+        self.opening_plies = plies;
+        self
+    }
+
+    pub fn with_move_limit(mut self, plies: usize) -> Self {
+        self.move_limit = plies;
+        self
+    }
+
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let n = self.entrants.len();
+        match self.format {
+            TournamentFormat::RoundRobin => (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .collect(),
+            TournamentFormat::Gauntlet => (1..n).map(|j| (0, j)).collect(),
+        }
+    }
+
+    pub fn schedule(&self) -> Vec<(usize, usize, usize)> {
+        //! (white, black, opening) for every game. Games come in pairs sharing an opening with swapped colors.
+        let mut ret = Vec::new();
+        for (i, j) in self.pairings() {
+            for game in 0..self.games_per_pairing {
+                let opening = game / 2;
+                if game % 2 == 0 {
+                    ret.push((i, j, opening));
+                } else {
+                    ret.push((j, i, opening));
+                }
+            }
+        }
+        ret
+    }
+
+    pub fn play_game(&self, white: usize, black: usize, opening: &CheckersController, seed: u64) -> MatchResult {
+        let controller = CheckersController::with_idle_moves(
+            opening.board,
+            opening.get_white_queen_idle_moves(),
+            opening.get_black_queen_idle_moves(),
+        );
+        let mut game = Game::new(controller, self.entrants[white].create(), self.entrants[black].create());
+        game.set_observer(Box::new(NullObserver));
+        game.set_move_limit(self.move_limit);
+        game.set_seed(seed);
+        let outcome = game.run();
+        MatchResult { white, black, outcome, plies: game.record().len() }
+    }
+
+    pub fn run(&self) -> TournamentReport {
+        let schedule = self.schedule();
+        let num_openings = schedule.iter().map(|&(_, _, opening)| opening + 1).max().unwrap_or(0);
+        let mut rng = StdRng::seed_from_u64(self.scheduler.seed());
+        let openings: Vec<CheckersController> = (0..num_openings)
+            .map(|_| random_opening(self.opening_plies, &mut rng))
+            .collect();
+        let results = self.scheduler.run(schedule, |_, (white, black, opening), seed| {
+            self.play_game(white, black, &openings[opening], seed)
+        });
+        TournamentReport::new(self.entrants.iter().map(|e| e.name.clone()).collect(), results)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Tally {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Tally {
+    pub fn add(&mut self, score: f64) {
+        if score > 0.75 {
+            self.wins += 1;
+        } else if score > 0.25 {
+            self.draws += 1;
+        } else {
+            self.losses += 1;
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+}
+
+impl Display for Tally {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}-{}", self.wins, self.draws, self.losses)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Rating {
+    pub elo: f64,
+    /// Half width of the 95% confidence interval.
+    pub margin: f64,
+}
+
+pub fn elo_difference(score: f64) -> f64 {
+    //! Rating difference corresponding to the expected score `score` in (0, 1).
+    let score = score.clamp(1e-3, 1.0 - 1e-3);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+pub struct TournamentReport {
+    pub names: Vec<String>,
+    pub results: Vec<MatchResult>,
+    /// `cross_table[i][j]` is the tally of entrant `i` against entrant `j`.
+    pub cross_table: Vec<Vec<Tally>>,
+    pub ratings: Vec<Rating>,
+}
+
+impl TournamentReport {
+    pub fn new(names: Vec<String>, results: Vec<MatchResult>) -> Self {
+        let n = names.len();
+        let mut cross_table = vec![vec![Tally::default(); n]; n];
+        for result in &results {
+            cross_table[result.white][result.black].add(result.score_of(result.white).unwrap());
+            cross_table[result.black][result.white].add(result.score_of(result.black).unwrap());
+        }
+        let ratings = Self::compute_ratings(&cross_table, &results);
+        Self { names, results, cross_table, ratings }
+    }
+
+    pub fn totals(&self, entrant: usize) -> Tally {
+        self.cross_table[entrant].iter().fold(Tally::default(), |acc, tally| Tally {
+            wins: acc.wins + tally.wins,
+            draws: acc.draws + tally.draws,
+            losses: acc.losses + tally.losses,
+        })
+    }
+
+    fn compute_ratings(cross_table: &[Vec<Tally>], results: &[MatchResult]) -> Vec<Rating> {
+        // Maximum likelihood fit of the logistic Elo model. Every entrant gets one virtual draw
+        // against a 0 rated opponent so that perfect scores still converge to a finite rating.
+        let n = cross_table.len();
+        let mut elo = vec![0.0; n];
+        for _ in 0..10_000 {
+            let mut max_change: f64 = 0.0;
+            for i in 0..n {
+                let mut actual = 0.5;
+                let mut expected = expected_score(elo[i], 0.0);
+                let mut games = 1.0;
+                for j in 0..n {
+                    let tally = cross_table[i][j];
+                    actual += tally.score();
+                    expected += tally.games() as f64 * expected_score(elo[i], elo[j]);
+                    games += tally.games() as f64;
+                }
+                let change = 400.0 * (actual - expected) / games;
+                elo[i] += change;
+                max_change = max_change.max(change.abs());
+            }
+            if max_change < 1e-3 {
+                break;
+            }
+        }
+        let mean = elo.iter().sum::<f64>() / n.max(1) as f64;
+        (0..n)
+            .map(|i| {
+                let scores: Vec<f64> = results.iter().filter_map(|r| r.score_of(i)).collect();
+                Rating { elo: elo[i] - mean, margin: Self::confidence_margin(&scores) }
+            })
+            .collect()
+    }
+
+    fn confidence_margin(scores: &[f64]) -> f64 {
+        //! Half the Elo width of the Wilson score interval around the average score. Unlike an interval from the
+        //! spread of the scores it does not shrink to nothing for a perfect score, where it reaches up to 1.
+        if scores.is_empty() {
+            return f64::INFINITY;
+        }
+        let (games, z) = (scores.len() as f64, 1.96);
+        let mean = scores.iter().sum::<f64>() / games;
+        let shrink = 1.0 + z * z / games;
+        let center = (mean + z * z / (2.0 * games)) / shrink;
+        let deviation = z / shrink * (mean * (1.0 - mean) / games + z * z / (4.0 * games * games)).sqrt();
+        (elo_difference(center + deviation) - elo_difference(center - deviation)) / 2.0
+    }
+
+    pub fn average_plies(&self) -> f64 {
+        self.results.iter().map(|result| result.plies as f64).sum::<f64>() / self.results.len().max(1) as f64
+    }
+
+    pub fn write_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for TournamentReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self.names.iter().map(|name| name.len() + 4).max().unwrap_or(0).max(6);
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| self.ratings[b].elo.partial_cmp(&self.ratings[a].elo).unwrap());
+
+        writeln!(f, "Games played: {}, average length {:.1} plies", self.results.len(), self.average_plies())?;
+        writeln!(f)?;
+        writeln!(f, "{:>4}  {:<width$}  {:>8}  {:>7}  {:>5}  {:>11}  {:>6}", "rank", "name", "elo", "+/-", "games", "W-D-L", "score")?;
+        for (rank, &i) in order.iter().enumerate() {
+            let totals = self.totals(i);
+            let score = if totals.games() == 0 { 0.0 } else { 100.0 * totals.score() / totals.games() as f64 };
+            writeln!(
+                f,
+                "{:>4}  {:<width$}  {:>8.1}  {:>7.1}  {:>5}  {:>11}  {:>5.1}%",
+                rank + 1, self.names[i], self.ratings[i].elo, self.ratings[i].margin, totals.games(), totals.to_string(), score
+            )?;
+        }
+        writeln!(f)?;
+        write!(f, "{:<width$}", "W-D-L")?;
+        for &j in &order {
+            write!(f, "  {:>10}", j + 1)?;
+        }
+        writeln!(f)?;
+        for &i in &order {
+            write!(f, "{:<width$}", format!("{}. {}", i + 1, self.names[i]))?;
+            for &j in &order {
+                let cell = if i == j || self.cross_table[i][j].games() == 0 {
+                    String::from("-")
+                } else {
+                    self.cross_table[i][j].to_string()
+                };
+                write!(f, "  {cell:>10}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_scores_are_not_certain() {
+        let perfect = TournamentReport::confidence_margin(&[1.0; 8]);
+        let even = TournamentReport::confidence_margin(&[1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+        assert!(perfect.is_finite() && perfect > even, "perfect {perfect}, even {even}");
+        assert!((TournamentReport::confidence_margin(&[0.0; 8]) - perfect).abs() < 1e-9);
+        assert!(TournamentReport::confidence_margin(&[1.0; 32]) < perfect, "more games narrow the interval");
+    }
+}
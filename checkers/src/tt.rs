@@ -0,0 +1,166 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::controller::{CheckersColor, CheckersController};
+use crate::scheduler::derive_seed;
+
+pub fn position_key(controller: &CheckersController, side_to_move: CheckersColor) -> u64 {
+    //! `Board::position_hash` mixed with the idle queen move counters, which decide games as well.
+    let idle = (controller.get_white_queen_idle_moves() as u64) << 8 | controller.get_black_queen_idle_moves() as u64;
+    derive_seed(controller.board.position_hash(side_to_move), idle)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least the stored one.
+    Lower,
+    /// The score is at most the stored one.
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TtEntry {
+    /// Score from the point of view of the side to move, wins and losses counted in plies from this position.
+    pub score: f64,
+    pub depth: u8,
+    pub bound: Bound,
+    /// Index of the best action in the order `CheckersController::options` returns them.
+    pub best: u8,
+}
+
+impl TtEntry {
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        1 << 24 | (self.best as u64) << 16 | bound << 8 | self.depth as u64
+    }
+
+    fn unpack(score: u64, meta: u64) -> Option<Self> {
+        if meta >> 24 == 0 {
+            return None;
+        }
+        let bound = match (meta >> 8) & 0xff {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        Some(Self { score: f64::from_bits(score), depth: meta as u8, bound, best: (meta >> 16) as u8 })
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    /// Key xor score xor meta, so torn writes by racing threads are detected and ignored.
+    check: AtomicU64,
+    score: AtomicU64,
+    meta: AtomicU64,
+}
+
+/// Fixed size hash table of search results shared by all search threads without locking.
+pub struct TranspositionTable {
+    slots: Box<[Slot]>,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_ENTRIES: usize = 1 << 20;
+
+    pub fn new(entries: usize) -> Self {
+        //! The number of entries is rounded up to a power of two, every entry takes 24 bytes.
+        let entries = entries.max(1).next_power_of_two();
+        Self { slots: (0..entries).map(|_| Slot::default()).collect() }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = self.slot(key);
+        let check = slot.check.load(Ordering::Relaxed);
+        let score = slot.score.load(Ordering::Relaxed);
+        let meta = slot.meta.load(Ordering::Relaxed);
+        if check ^ score ^ meta != key {
+            return None;
+        }
+        TtEntry::unpack(score, meta)
+    }
+
+    pub fn store(&self, key: u64, entry: TtEntry) {
+        //! Deeper results are kept over shallower ones of the same position, other positions are replaced.
+        if let Some(old) = self.probe(key) {
+            if old.depth > entry.depth {
+                return;
+            }
+        }
+        let slot = self.slot(key);
+        let score = entry.score.to_bits();
+        let meta = entry.pack();
+        slot.score.store(score, Ordering::Relaxed);
+        slot.meta.store(meta, Ordering::Relaxed);
+        slot.check.store(key ^ score ^ meta, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: f64, depth: u8, bound: Bound, best: u8) -> TtEntry {
+        TtEntry { score, depth, bound, best }
+    }
+
+    fn assert_same(actual: Option<TtEntry>, expected: TtEntry) {
+        let actual = actual.expect("the entry should be found");
+        assert_eq!(actual.score.to_bits(), expected.score.to_bits());
+        assert_eq!((actual.depth, actual.bound, actual.best), (expected.depth, expected.bound, expected.best));
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        for expected in [
+            entry(0.0, 0, Bound::Exact, 0),
+            entry(-1.25, 7, Bound::Lower, 3),
+            entry(1e10 - 5.0, 255, Bound::Upper, 255),
+        ] {
+            assert_same(TtEntry::unpack(expected.score.to_bits(), expected.pack()), expected);
+        }
+        assert!(TtEntry::unpack(0, 0).is_none(), "empty slots hold no entry");
+    }
+
+    #[test]
+    fn torn_writes_are_ignored() {
+        let table = TranspositionTable::new(16);
+        let key = 0x1234_5678_9abc_def0;
+        table.store(key, entry(0.5, 4, Bound::Exact, 1));
+        assert_same(table.probe(key), entry(0.5, 4, Bound::Exact, 1));
+
+        // another thread got as far as replacing the score, but not the check
+        table.slot(key).score.store(2.5f64.to_bits(), Ordering::Relaxed);
+        assert!(table.probe(key).is_none());
+
+        // a different position mapping to the same slot
+        assert!(table.probe(key ^ 1 << 40).is_none());
+    }
+
+    #[test]
+    fn deeper_entries_are_kept() {
+        let table = TranspositionTable::new(16);
+        let key = 42;
+        table.store(key, entry(1.0, 6, Bound::Exact, 2));
+        table.store(key, entry(2.0, 3, Bound::Lower, 0));
+        assert_same(table.probe(key), entry(1.0, 6, Bound::Exact, 2));
+
+        table.store(key, entry(3.0, 6, Bound::Upper, 1));
+        assert_same(table.probe(key), entry(3.0, 6, Bound::Upper, 1));
+        table.store(key, entry(4.0, 8, Bound::Exact, 5));
+        assert_same(table.probe(key), entry(4.0, 8, Bound::Exact, 5));
+
+        // other positions replace the slot regardless of depth
+        let other = key + 16;
+        table.store(other, entry(5.0, 1, Bound::Exact, 0));
+        assert_same(table.probe(other), entry(5.0, 1, Bound::Exact, 0));
+        assert!(table.probe(key).is_none());
+    }
+}
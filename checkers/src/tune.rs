@@ -0,0 +1,171 @@
+use std::fmt::{Display, Formatter};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use crate::game::estimators::BoardEstimator;
+use crate::game::player::AlphaBetaBot;
+use crate::scheduler::MatchScheduler;
+use crate::tournament::{Entrant, Tournament, TournamentFormat};
+
+/// An estimator whose weights can be read and replaced as a flat list of parameters.
+pub trait Tunable: BoardEstimator + Clone + Send + Sync + 'static {
+    fn parameters(&self) -> Vec<f64>;
+    fn with_parameters(&self, parameters: &[f64]) -> Self;
+}
+
+impl <T: Tunable> Tunable for Box<T> {
+    fn parameters(&self) -> Vec<f64> {
+        (**self).parameters()
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        Box::new((**self).with_parameters(parameters))
+    }
+}
+
+/// Scores of one generation, the convergence log has one per generation.
+pub struct GenerationSummary {
+    pub generation: usize,
+    /// Share of points the best individual scored in the generation's round robin.
+    pub best_score: f64,
+    pub mean_score: f64,
+    pub best_parameters: Vec<f64>,
+}
+
+impl Display for GenerationSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parameters: Vec<String> = self.best_parameters.iter().map(|p| format!("{p:.3}")).collect();
+        write!(
+            f, "generation {}: best {:.1}%, mean {:.1}%, parameters [{}]",
+            self.generation, self.best_score * 100.0, self.mean_score * 100.0, parameters.join(", ")
+        )
+    }
+}
+
+/// Evolves estimator weights: every generation plays a round robin between `AlphaBetaBot`s using the
+/// individuals, the best half survives and the rest is replaced by mutated crossovers of the survivors.
+pub struct GeneticTuner {
+    population: usize,
+    generations: usize,
+    depth: usize,
+    games_per_pairing: usize,
+    /// Mutations change a parameter by up to this fraction of its value.
+    mutation: f64,
+    scheduler: MatchScheduler,
+}
+
+impl GeneticTuner {
+    pub fn new(generations: usize, scheduler: MatchScheduler) -> Self {
+        Self { population: 8, generations, depth: 4, games_per_pairing: 2, mutation: 0.2, scheduler }
+    }
+
+    pub fn with_population(mut self, population: usize) -> Self {
+        self.population = population.max(2);
+        self
+    }
+
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_games_per_pairing(mut self, games: usize) -> Self {
+        self.games_per_pairing = games;
+        self
+    }
+
+    fn mutate<R: Rng>(&self, parameters: &mut [f64], rng: &mut R) {
+        for parameter in parameters {
+            // parameters near 0 still have to be able to move away from it
+            let scale = parameter.abs().max(0.1) * self.mutation;
+            *parameter += rng.gen_range(-scale..=scale);
+        }
+    }
+
+    fn crossover<R: Rng>(first: &[f64], second: &[f64], rng: &mut R) -> Vec<f64> {
+        first.iter().zip(second).map(|(&a, &b)| if rng.gen() { a } else { b }).collect()
+    }
+
+    fn evaluate<T: Tunable>(&self, individuals: &[Vec<f64>], base: &T, generation: usize) -> Vec<f64> {
+        //! Share of points every individual scored in a round robin.
+        let depth = self.depth;
+        let entrants = individuals
+            .iter()
+            .enumerate()
+            .map(|(i, parameters)| {
+                let estimator = base.with_parameters(parameters);
+                Entrant::new(&format!("#{i}"), move || Box::new(AlphaBetaBot::new(estimator.clone(), depth)))
+            })
+            .collect();
+        let report = Tournament::new(entrants, TournamentFormat::RoundRobin)
+            .with_games_per_pairing(self.games_per_pairing)
+            .with_scheduler(self.scheduler.round(generation as u64))
+            .run();
+        (0..individuals.len())
+            .map(|i| {
+                let tally = report.totals(i);
+                tally.score() / tally.games().max(1) as f64
+            })
+            .collect()
+    }
+
+    pub fn run<T: Tunable>(&self, base: T, mut log: impl FnMut(&GenerationSummary)) -> T {
+        //! Starts from `base` and mutations of it, calls `log` after every generation and returns the best
+        //! individual of the last one.
+        let mut rng = StdRng::seed_from_u64(self.scheduler.seed());
+        let mut individuals = vec![base.parameters()];
+        while individuals.len() < self.population {
+            let mut parameters = base.parameters();
+            self.mutate(&mut parameters, &mut rng);
+            individuals.push(parameters);
+        }
+        let mut best = base.parameters();
+        for generation in 1..=self.generations {
+            let scores = self.evaluate(&individuals, &base, generation);
+            let mut ranking: Vec<usize> = (0..individuals.len()).collect();
+            ranking.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+            best = individuals[ranking[0]].clone();
+            log(&GenerationSummary {
+                generation,
+                best_score: scores[ranking[0]],
+                mean_score: scores.iter().sum::<f64>() / scores.len() as f64,
+                best_parameters: best.clone(),
+            });
+            if generation == self.generations {
+                break;
+            }
+            let survivors: Vec<Vec<f64>> = ranking[..self.population / 2].iter().map(|&i| individuals[i].clone()).collect();
+            individuals = survivors.clone();
+            while individuals.len() < self.population {
+                let first = survivors.choose(&mut rng).unwrap();
+                let second = survivors.choose(&mut rng).unwrap();
+                let mut child = Self::crossover(first, second, &mut rng);
+                self.mutate(&mut child, &mut rng);
+                individuals.push(child);
+            }
+        }
+        base.with_parameters(&best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EstimatorConfig;
+
+    #[test]
+    fn best_config_round_trips() {
+        let tuner = GeneticTuner::new(2, MatchScheduler::new(1, 1, 3)).with_population(2).with_depth(1).with_games_per_pairing(1);
+        let mut logged = Vec::new();
+        let best = tuner.run(EstimatorConfig::builtin("count").unwrap(), |summary| logged.push(summary.best_parameters.clone()));
+        assert_eq!(logged.len(), 2);
+        assert_eq!(best.parameters(), logged[1]);
+        for extension in ["toml", "json"] {
+            let path = std::env::temp_dir().join(format!("checkers-tuned-{}.{extension}", std::process::id()));
+            best.save(&path).unwrap();
+            let loaded = EstimatorConfig::load(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap().parameters(), best.parameters(), "{extension}");
+        }
+    }
+}